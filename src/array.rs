@@ -0,0 +1,712 @@
+use schemars::schema::{InstanceType, SchemaObject, SingleOrVec};
+use serde_json::Value;
+
+use crate::{
+    error::{SchemaError, SchemaResult},
+    get_bytes, get_title_str, parse_schema,
+    prompt::{Column, Constraints, Question, QuestionKind},
+    shape::{shape, Shape, Variant},
+    state::ParseState,
+    undo::clear_lines,
+    with_default,
+};
+
+/// Something the user can do to an array once it has elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Add,
+    AddSeveral,
+    Import,
+    Edit,
+    Duplicate,
+    MoveUp,
+    MoveDown,
+    Delete,
+    Done,
+}
+
+impl Action {
+    const ALL: [Action; 9] = [
+        Action::Add,
+        Action::AddSeveral,
+        Action::Import,
+        Action::Edit,
+        Action::Duplicate,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Delete,
+        Action::Done,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Action::Add => "Add element",
+            Action::AddSeveral => "Add several elements",
+            Action::Import => "Import elements from a CSV file",
+            Action::Edit => "Edit element",
+            Action::Duplicate => "Duplicate element",
+            Action::MoveUp => "Move element up",
+            Action::MoveDown => "Move element down",
+            Action::Delete => "Delete element",
+            Action::Done => "Done",
+        }
+    }
+}
+
+/// Builds an array whose elements all share one schema. Elements are added
+/// until `min_items` is reached, after which a menu lets the user add, edit,
+/// duplicate, reorder and delete elements until they are done. Arrays of
+/// strings, numbers or bools can also be entered several elements at a time,
+/// and arrays of flat objects imported from a CSV file. Arrays of unit enums
+/// are picked from a single multi-select instead, and arrays of bytes can be
+/// written as hex, base64 or text, or read from a file.
+pub(crate) struct ArrayMenu<'a> {
+    pub(crate) title: &'a Option<String>,
+    pub(crate) name: &'a str,
+    pub(crate) description: &'a str,
+    pub(crate) item: SchemaObject,
+    pub(crate) min_items: usize,
+    pub(crate) max_items: Option<usize>,
+    /// Whether elements must differ from each other, as in a set.
+    pub(crate) unique_items: bool,
+}
+
+impl ArrayMenu<'_> {
+    pub(crate) fn parse(&self, state: &ParseState) -> SchemaResult<Vec<Value>> {
+        if self.is_bytes() {
+            let constraints = Constraints {
+                min_items: Some(self.min_items as u32),
+                max_items: self.max_items.map(|max| max as u32),
+                unique_items: self.unique_items,
+                ..Default::default()
+            };
+            if let Some(bytes) = get_bytes(state, &self.help(), constraints, true)? {
+                return Ok(bytes);
+            }
+        }
+        match self.variants(state) {
+            Some(variants) => self.select(state, variants),
+            None => self.run(state, Vec::new()),
+        }
+    }
+
+    /// Whether the elements are bytes, as in a `Vec<u8>`.
+    fn is_bytes(&self) -> bool {
+        self.item.format.as_deref() == Some("uint8")
+            && self.item.instance_type == Some(SingleOrVec::Single(Box::new(InstanceType::Integer)))
+    }
+
+    /// The variants of elements that are unit enums, with the json value of
+    /// each.
+    fn variants(&self, state: &ParseState) -> Option<Vec<(String, Value)>> {
+        let (_, Shape::Enum(variants)) = shape(state.definitions, &self.item).ok()? else {
+            return None;
+        };
+        variants
+            .into_iter()
+            .map(|(name, variant)| match variant {
+                Variant::Unit(value) => Some((name, value)),
+                Variant::Contents(_) => None,
+            })
+            .collect()
+    }
+
+    /// Asks which of `variants` the array holds, all at once.
+    fn select(
+        &self,
+        state: &ParseState,
+        variants: Vec<(String, Value)>,
+    ) -> SchemaResult<Vec<Value>> {
+        let mut question = Question::new(QuestionKind::MultiSelect, "Select any:", self.help())
+            .with_choices(variants.iter().map(|(name, _)| name.clone()).collect());
+        question.constraints.min_items = Some(self.min_items as u32);
+        question.constraints.max_items = self.max_items.map(|max| max as u32);
+        let answer = state.ask(question)?;
+        let selected = answer.as_array().cloned().unwrap_or_default();
+        Ok(variants
+            .into_iter()
+            .filter(|(name, _)| selected.iter().any(|x| x == name.as_str()))
+            .map(|(_, value)| value)
+            .collect())
+    }
+
+    pub(crate) fn run(&self, state: &ParseState, array: Vec<Value>) -> SchemaResult<Vec<Value>> {
+        // The array before each step along with the depth the step started
+        // at, so that undoing a question goes back to the step that asked it.
+        let mut steps = Vec::new();
+        let mut current = (state.current_depth.get(), array);
+        loop {
+            match self.step(state, current.1.clone()) {
+                Ok(Some(next)) => {
+                    let depth_checkpoint = state.current_depth.get();
+                    steps.push(std::mem::replace(&mut current, (depth_checkpoint, next)));
+                }
+                Ok(None) => return Ok(current.1),
+                Err(SchemaError::Undo { depth }) => {
+                    while current.0 >= depth {
+                        current = steps.pop().ok_or(SchemaError::Undo { depth })?;
+                    }
+                    state.current_depth.set(current.0);
+                    clear_lines(depth - current.0 + 1);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Makes a single change to `array`, or returns `None` once the user is
+    /// done with it.
+    fn step(&self, state: &ParseState, mut array: Vec<Value>) -> SchemaResult<Option<Vec<Value>>> {
+        let len = array.len();
+        let can_add = self.max_items.is_none_or(|max| len < max);
+        if len < self.min_items {
+            array.push(self.element(state, &array, len)?);
+            return Ok(Some(array));
+        }
+        let element_question = self.element_question();
+        let columns = self.columns(state);
+        if len == 0 && element_question.is_none() && columns.is_none() {
+            if !can_add || !self.confirm_add(state)? {
+                return Ok(None);
+            }
+            array.push(self.element(state, &array, 0)?);
+            return Ok(Some(array));
+        }
+
+        let actions = Action::ALL
+            .into_iter()
+            .filter(|action| match action {
+                Action::Add => can_add,
+                Action::AddSeveral => can_add && element_question.is_some(),
+                Action::Import => can_add && columns.is_some(),
+                Action::Duplicate => can_add && len > 0 && !self.unique_items,
+                Action::MoveUp | Action::MoveDown => len > 1,
+                Action::Delete => len > self.min_items,
+                Action::Edit => len > 0,
+                Action::Done => true,
+            })
+            .collect::<Vec<_>>();
+        let answer = state.ask(
+            Question::new(QuestionKind::Select, "What next?", self.help()).with_choices(
+                actions
+                    .iter()
+                    .map(|action| action.label().to_string())
+                    .collect(),
+            ),
+        )?;
+        let action = actions
+            .into_iter()
+            .find(|action| answer.as_str() == Some(action.label()))
+            .ok_or_else(|| not_a_choice(state, &answer))?;
+
+        match action {
+            Action::Add => array.push(self.element(state, &array, len)?),
+            Action::AddSeveral => {
+                let mut question = Question::new(
+                    QuestionKind::String,
+                    "Elements:",
+                    "separate elements with commas or new lines, or enter @file to read them from a file",
+                );
+                question.constraints.max_items = self.max_items.map(|max| (max - len) as u32);
+                question.elements = element_question.map(Box::new);
+                let answer = self.ask_unique(state, &array, question)?;
+                array.extend(answer.as_array().cloned().unwrap_or_default());
+            }
+            Action::Import => {
+                let mut question = Question::new(
+                    QuestionKind::String,
+                    "CSV file:",
+                    "the header row names the properties of each element",
+                );
+                question.constraints.max_items = self.max_items.map(|max| (max - len) as u32);
+                question.columns = columns;
+                let answer = self.ask_unique(state, &array, question)?;
+                array.extend(answer.as_array().cloned().unwrap_or_default());
+            }
+            Action::Edit => {
+                let i = self.pick(state, "Edit which element?", &array, 0..len)?;
+                array[i] = self.element(state, &array, i)?;
+            }
+            Action::Duplicate => {
+                let i = self.pick(state, "Duplicate which element?", &array, 0..len)?;
+                // The copy is edited straight away, starting from the original.
+                let item = with_default(self.item.clone(), &array[i]);
+                let copy = ArrayMenu { item, ..*self }.element(state, &array, len)?;
+                array.push(copy);
+            }
+            Action::MoveUp => {
+                let i = self.pick(state, "Move which element up?", &array, 1..len)?;
+                array.swap(i - 1, i);
+            }
+            Action::MoveDown => {
+                let i = self.pick(state, "Move which element down?", &array, 0..len - 1)?;
+                array.swap(i, i + 1);
+            }
+            Action::Delete => {
+                let i = self.pick(state, "Delete which element?", &array, 0..len)?;
+                array.remove(i);
+            }
+            Action::Done => return Ok(None),
+        }
+        // Elements may have moved, so the partial value needs the whole array.
+        state.complete(&Value::Array(array.clone()));
+        Ok(Some(array))
+    }
+
+    fn help(&self) -> String {
+        format!(
+            "{}{}{}",
+            get_title_str(self.title),
+            self.name,
+            self.description
+        )
+    }
+
+    /// The question each element answers, if elements are simple enough to
+    /// be entered several at a time.
+    fn element_question(&self) -> Option<Question> {
+        let kind = match &self.item.instance_type {
+            _ if self.item.enum_values.is_some() => return None,
+            Some(SingleOrVec::Single(instance_type)) => match **instance_type {
+                InstanceType::String => QuestionKind::String,
+                InstanceType::Integer => QuestionKind::Integer,
+                InstanceType::Number => QuestionKind::Number,
+                InstanceType::Boolean => QuestionKind::Boolean,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(Question::new(kind, self.name, "").with_schema(&self.item))
+    }
+
+    /// The columns of a CSV file to import elements from, if elements are
+    /// objects whose properties are all strings, numbers, bools or unit
+    /// enums, or optional ones.
+    fn columns(&self, state: &ParseState) -> Option<Vec<Column>> {
+        let (_, Shape::Object(properties)) = shape(state.definitions, &self.item).ok()? else {
+            return None;
+        };
+        properties
+            .into_iter()
+            .map(|(name, schema)| {
+                let (mut schema, mut property_shape) = shape(state.definitions, &schema).ok()?;
+                let optional = matches!(property_shape, Shape::Optional(_));
+                if let Shape::Optional(inner) = property_shape {
+                    (schema, property_shape) = shape(state.definitions, &inner).ok()?;
+                }
+                let question = match property_shape {
+                    Shape::Leaf(kind) => Question::new(kind, &name, "").with_schema(&schema),
+                    Shape::Enum(variants)
+                        if variants
+                            .iter()
+                            .all(|(_, variant)| matches!(variant, Variant::Unit(_))) =>
+                    {
+                        Question::new(QuestionKind::Select, &name, "")
+                            .with_choices(variants.into_iter().map(|(name, _)| name).collect())
+                    }
+                    _ => return None,
+                };
+                Some(Column {
+                    name,
+                    question,
+                    optional,
+                })
+            })
+            .collect()
+    }
+
+    fn confirm_add(&self, state: &ParseState) -> SchemaResult<bool> {
+        Ok(state
+            .ask(Question::new(
+                QuestionKind::Confirm,
+                "Add element?",
+                self.help(),
+            ))?
+            .as_bool()
+            .unwrap())
+    }
+
+    /// Parses the element at index `i`, which is either replaced or, at the
+    /// end of `array`, added.
+    fn element(&self, state: &ParseState, array: &[Value], i: usize) -> SchemaResult<Value> {
+        state.with_segment(i, || {
+            state.check(
+                || {
+                    parse_schema(
+                        state,
+                        self.title.clone(),
+                        format!("{}[{}]", self.name, i),
+                        self.item.clone(),
+                    )
+                },
+                |element| {
+                    let mut array = array.to_vec();
+                    match array.get_mut(i) {
+                        Some(old) => *old = element.clone(),
+                        None => array.push(element.clone()),
+                    }
+                    self.check_unique(array)
+                },
+            )
+        })
+    }
+
+    /// Asks `question` for several elements to add to `array`.
+    fn ask_unique(
+        &self,
+        state: &ParseState,
+        array: &[Value],
+        question: Question,
+    ) -> SchemaResult<Value> {
+        state.check(
+            || state.ask(question.clone()),
+            |answer| {
+                let mut array = array.to_vec();
+                array.extend(answer.as_array().cloned().unwrap_or_default());
+                self.check_unique(array)
+            },
+        )
+    }
+
+    fn check_unique(&self, array: Vec<Value>) -> Result<(), String> {
+        Constraints {
+            unique_items: self.unique_items,
+            ..Default::default()
+        }
+        .check(&Value::Array(array))
+    }
+
+    /// Asks which of the elements at `indices` to act on.
+    fn pick(
+        &self,
+        state: &ParseState,
+        message: &str,
+        array: &[Value],
+        indices: std::ops::Range<usize>,
+    ) -> SchemaResult<usize> {
+        let choices = indices
+            .clone()
+            .map(|i| format!("[{i}] {}", summary(&array[i])))
+            .collect::<Vec<_>>();
+        let answer = state.ask(
+            Question::new(QuestionKind::Select, message, self.help()).with_choices(choices.clone()),
+        )?;
+        let position = choices
+            .iter()
+            .position(|choice| answer.as_str() == Some(choice))
+            .ok_or_else(|| not_a_choice(state, &answer))?;
+        Ok(indices.start + position)
+    }
+}
+
+/// The error for an answer to a menu that isn't one of its choices.
+fn not_a_choice(state: &ParseState, answer: &Value) -> SchemaError {
+    SchemaError::InvalidAnswer {
+        path: state.pointer(),
+        message: format!("{answer} is not one of the choices"),
+    }
+}
+
+/// A one line summary of an element for the element picker.
+fn summary(value: &Value) -> String {
+    const MAX_CHARS: usize = 60;
+    let text = value.to_string();
+    if text.chars().count() <= MAX_CHARS {
+        return text;
+    }
+    let mut summary = text.chars().take(MAX_CHARS - 1).collect::<String>();
+    summary.push('…');
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{error::SchemaError, QuestionKind, Session};
+
+    #[test]
+    fn test_array_menu() {
+        let mut session = Session::for_type::<Vec<u32>>().unwrap();
+        let mut answer = |answer| {
+            session.answer(answer).unwrap();
+            session.next_question()
+        };
+        answer(json!("Add element"));
+        answer(json!(1));
+        answer(json!("Add element"));
+        let question = answer(json!(2)).unwrap();
+        assert_eq!(question.kind, QuestionKind::Select);
+        assert_eq!(
+            question.choices,
+            vec![
+                "Add element",
+                "Add several elements",
+                "Edit element",
+                "Duplicate element",
+                "Move element up",
+                "Move element down",
+                "Delete element",
+                "Done",
+            ]
+        );
+
+        answer(json!("Duplicate element"));
+        // The copy is opened for editing, with the original as its default.
+        let question = answer(json!("[0] 1")).unwrap();
+        assert_eq!(question.path, "/2");
+        assert_eq!(question.default, Some(json!(1)));
+        answer(json!(1));
+        answer(json!("Move element up"));
+        let question = answer(json!("[2] 1")).unwrap();
+        assert_eq!(question.path, "");
+        answer(json!("Edit element"));
+        let question = answer(json!("[0] 1")).unwrap();
+        assert_eq!(question.path, "/0");
+        answer(json!(3));
+        answer(json!("Delete element"));
+        let question = answer(json!("[2] 2"));
+        assert!(question.is_some());
+        assert_eq!(session.partial(), json!([3, 1]));
+        session.answer(json!("Done")).unwrap();
+        assert_eq!(session.value(), Some(&json!([3, 1])));
+    }
+
+    #[test]
+    fn test_duplicate_struct() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Server {
+            name: String,
+            port: u16,
+        }
+
+        let mut session = Session::for_type::<Vec<Server>>().unwrap();
+        for answer in [
+            json!("Add element"),
+            json!("a"),
+            json!(80),
+            json!("Duplicate element"),
+        ] {
+            session.answer(answer).unwrap();
+        }
+        session
+            .answer(json!(r#"[0] {"name":"a","port":80}"#))
+            .unwrap();
+        // Every field of the copy starts from the original.
+        let question = session.next_question().unwrap();
+        assert_eq!(question.path, "/1/name");
+        assert_eq!(question.default, Some(json!("a")));
+        session.answer(json!("b")).unwrap();
+        assert_eq!(session.next_question().unwrap().default, Some(json!(80)));
+        session.answer(json!(80)).unwrap();
+        session.answer(json!("Done")).unwrap();
+        assert_eq!(
+            session.value(),
+            Some(&json!([{ "name": "a", "port": 80 }, { "name": "b", "port": 80 }]))
+        );
+    }
+
+    #[test]
+    fn test_array_menu_limits() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Pair {
+            #[schemars(length(min = 1, max = 2))]
+            items: Vec<bool>,
+        }
+
+        // The first element is required, so it is asked for straight away.
+        let mut session = Session::for_type::<Pair>().unwrap();
+        assert_eq!(session.next_question().unwrap().path, "/items/0");
+        session.answer(json!(true)).unwrap();
+        // A single required element can be neither deleted nor moved.
+        assert_eq!(
+            session.next_question().unwrap().choices,
+            vec![
+                "Add element",
+                "Add several elements",
+                "Edit element",
+                "Duplicate element",
+                "Done",
+            ]
+        );
+        session.answer(json!("Duplicate element")).unwrap();
+        session.answer(json!("[0] true")).unwrap();
+        session.answer(json!(false)).unwrap();
+        // The array is full.
+        assert_eq!(
+            session.next_question().unwrap().choices,
+            vec![
+                "Edit element",
+                "Move element up",
+                "Move element down",
+                "Delete element",
+                "Done",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_add_several_elements() {
+        let mut session = Session::for_type::<Vec<u8>>().unwrap();
+        session.answer(json!("One element at a time")).unwrap();
+        session.answer(json!("Add several elements")).unwrap();
+        let question = session.next_question().unwrap();
+        assert!(question.elements.is_some());
+        assert_eq!(
+            question.validate(&json!("1, 2,\n3, 300")),
+            Err("element [3]: must be at most 255".to_string())
+        );
+        session.answer(json!("1, 2,\n3")).unwrap();
+        session.answer(json!("Add several elements")).unwrap();
+
+        let path = std::env::temp_dir().join("interactive-parse-test-elements.csv");
+        std::fs::write(&path, "4\n5\n").unwrap();
+        session
+            .answer(json!(format!("@{}", path.display())))
+            .unwrap();
+        std::fs::remove_file(path).unwrap();
+        session.answer(json!("Done")).unwrap();
+        assert_eq!(session.value(), Some(&json!([1, 2, 3, 4, 5])));
+    }
+
+    #[test]
+    fn test_import_csv() {
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        enum Role {
+            Admin,
+            User,
+        }
+
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct Account {
+            name: String,
+            age: u8,
+            role: Option<Role>,
+        }
+
+        let path = std::env::temp_dir().join("interactive-parse-test-accounts.csv");
+        let mut session = Session::for_type::<Vec<Account>>().unwrap();
+        session
+            .answer(json!("Import elements from a CSV file"))
+            .unwrap();
+
+        std::fs::write(&path, "name,age,role\nada,36,Admin\nbob,300,\n").unwrap();
+        let question = session.next_question().unwrap();
+        assert_eq!(
+            question.validate(&json!(path.display().to_string())),
+            Err("row 3, column age: must be at most 255".to_string())
+        );
+
+        std::fs::write(&path, "age,name\n36,ada\n25,bob\n").unwrap();
+        session.answer(json!(path.display().to_string())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        session.answer(json!("Done")).unwrap();
+        assert_eq!(
+            session.value(),
+            Some(&json!([
+                { "name": "ada", "age": 36, "role": null },
+                { "name": "bob", "age": 25, "role": null },
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_multi_select() {
+        #[derive(schemars::JsonSchema, PartialEq, Eq, PartialOrd, Ord)]
+        #[allow(dead_code)]
+        enum Flag {
+            Read,
+            Write,
+            Execute,
+        }
+
+        #[derive(schemars::JsonSchema)]
+        #[allow(dead_code)]
+        struct File {
+            #[schemars(length(max = 2))]
+            flags: std::collections::BTreeSet<Flag>,
+        }
+
+        let mut session = Session::for_type::<File>().unwrap();
+        let question = session.next_question().unwrap();
+        assert_eq!(question.kind, QuestionKind::MultiSelect);
+        assert_eq!(question.choices, vec!["Read", "Write", "Execute"]);
+        assert_eq!(
+            question.validate(&json!(["Read", "Write", "Execute"])),
+            Err("must have at most 2 elements".to_string())
+        );
+        assert_eq!(
+            question.parse("Execute, Read"),
+            Ok(json!(["Execute", "Read"]))
+        );
+        session.answer(json!(["Execute", "Read"])).unwrap();
+        assert_eq!(
+            session.value(),
+            Some(&json!({ "flags": ["Read", "Execute"] }))
+        );
+    }
+
+    #[test]
+    fn test_unique_items() {
+        let mut session = Session::for_type::<std::collections::BTreeSet<String>>().unwrap();
+        session.answer(json!("Add element")).unwrap();
+        session.answer(json!("a")).unwrap();
+        let question = session.next_question().unwrap();
+        assert!(!question.choices.iter().any(|x| x == "Duplicate element"));
+
+        session.answer(json!("Add element")).unwrap();
+        match session.answer(json!("a")) {
+            Err(SchemaError::InvalidAnswer { path, message }) => {
+                assert_eq!(path, "/1");
+                assert_eq!(message, "\"a\" is already in the list");
+            }
+            other => panic!("expected a rejected answer, got {other:?}"),
+        }
+        assert_eq!(session.next_question().unwrap().path, "/1");
+        session.answer(json!("b")).unwrap();
+
+        session.answer(json!("Add several elements")).unwrap();
+        assert!(session.answer(json!("c, b")).is_err());
+        session.answer(json!("c, d")).unwrap();
+        session.answer(json!("Done")).unwrap();
+        assert_eq!(session.value(), Some(&json!(["a", "b", "c", "d"])));
+    }
+
+    #[test]
+    fn test_bytes() {
+        let mut session = Session::for_type::<Vec<u8>>().unwrap();
+        assert_eq!(
+            session.next_question().unwrap().choices,
+            vec![
+                "Hex",
+                "Base64",
+                "UTF-8 text",
+                "Read from a file",
+                "One element at a time",
+            ]
+        );
+        session.answer(json!("Hex")).unwrap();
+        let question = session.next_question().unwrap();
+        assert_eq!(
+            question.validate(&json!("0x4")),
+            Err("hex must have an even number of digits".to_string())
+        );
+        session.answer(json!("48:69 ff")).unwrap();
+        assert_eq!(session.value(), Some(&json!([0x48, 0x69, 0xff])));
+
+        let mut session = Session::for_type::<Vec<u8>>().unwrap();
+        session.answer(json!("UTF-8 text")).unwrap();
+        session.answer(json!("Hi")).unwrap();
+        assert_eq!(session.value(), Some(&json!([0x48, 0x69])));
+
+        let mut session = Session::for_type::<std::collections::BTreeSet<u8>>().unwrap();
+        session.answer(json!("Hex")).unwrap();
+        assert!(session.answer(json!("01 02 01")).is_err());
+        session.answer(json!("02 01")).unwrap();
+        assert_eq!(session.value(), Some(&json!([2, 1])));
+    }
+}
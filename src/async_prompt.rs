@@ -0,0 +1,222 @@
+use std::future::Future;
+
+use inquire::InquireError;
+use schemars::schema::RootSchema;
+use serde_json::Value;
+
+use crate::{
+    error::{SchemaError, SchemaResult},
+    options::ParseOptions,
+    prompt::{prompt_terminal, Question},
+    session::Session,
+    undo::clear_lines,
+};
+
+/// Answers questions from an async source.
+pub trait AsyncPrompter {
+    /// Answers `question`. `Ok(None)` takes back the previous answer, the
+    /// same way hitting `Esc` does on the terminal. A rejected answer, such
+    /// as an element that is already in a set, is asked for again with the
+    /// reason at the start of the question's help.
+    fn ask(
+        &mut self,
+        question: &Question,
+    ) -> impl Future<Output = SchemaResult<Option<Value>>> + Send;
+}
+
+/// Prompts on the terminal from a blocking task, so the runtime thread is
+/// never blocked.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TerminalPrompter;
+
+impl AsyncPrompter for TerminalPrompter {
+    async fn ask(&mut self, question: &Question) -> SchemaResult<Option<Value>> {
+        let question = question.clone();
+        let answer = tokio::task::spawn_blocking(move || prompt_terminal(&question))
+            .await
+            .map_err(|e| SchemaError::Generic(e.to_string()))??;
+        if answer.is_none() {
+            // Clear the cancelled prompt and the answer being taken back.
+            clear_lines(2);
+        }
+        Ok(answer)
+    }
+}
+
+pub(crate) async fn parse_async<P>(
+    root_schema: RootSchema,
+    options: ParseOptions,
+    prompter: &mut P,
+) -> SchemaResult<Value>
+where
+    P: AsyncPrompter + Send,
+{
+    let mut session =
+        tokio::task::spawn_blocking(move || Session::with_options(root_schema, options))
+            .await
+            .map_err(|e| SchemaError::Generic(e.to_string()))??;
+    let mut rejection = None;
+    loop {
+        let Some(mut question) = session.next_question() else {
+            return Ok(session.value().cloned().unwrap_or_default());
+        };
+        if let Some(message) = rejection.take() {
+            question = question.with_notice(format!("{message}, please try again"));
+        }
+        match prompter.ask(&question).await {
+            Ok(Some(answer)) => {
+                let result;
+                (session, result) =
+                    blocking(session, move |session| session.answer(answer)).await?;
+                match result {
+                    Err(SchemaError::InvalidAnswer { message, .. }) => rejection = Some(message),
+                    result => result?,
+                }
+            }
+            Ok(None) => {
+                let result;
+                (session, result) = blocking(session, Session::back).await?;
+                result?;
+            }
+            Err(SchemaError::Inquire(
+                InquireError::OperationInterrupted | InquireError::OperationCanceled,
+            )) => {
+                return Err(SchemaError::Exit {
+                    partial: session.redact(&session.partial()),
+                    path: question.path.clone(),
+                })
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Runs `f` on `session` from a blocking task. Every step replays the
+/// answers so far, which runs the choice providers and validators again, and
+/// those may well block.
+async fn blocking<T: Send + 'static>(
+    mut session: Session,
+    f: impl FnOnce(&mut Session) -> T + Send + 'static,
+) -> SchemaResult<(Session, T)> {
+    tokio::task::spawn_blocking(move || {
+        let result = f(&mut session);
+        (session, result)
+    })
+    .await
+    .map_err(|e| SchemaError::Generic(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+
+    use super::AsyncPrompter;
+    use crate::{error::SchemaResult, InteractiveParseObj, ParseOptions, Question};
+
+    #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: String,
+        ports: Vec<u16>,
+    }
+
+    #[derive(Default)]
+    struct Scripted {
+        answers: Vec<Option<Value>>,
+        helps: Vec<String>,
+    }
+
+    impl Scripted {
+        fn new(answers: Vec<Option<Value>>) -> Self {
+            Self {
+                answers,
+                ..Default::default()
+            }
+        }
+    }
+
+    impl AsyncPrompter for Scripted {
+        async fn ask(&mut self, question: &Question) -> SchemaResult<Option<Value>> {
+            self.helps.push(question.help.clone());
+            Ok(self.answers.remove(0))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_to_obj_async() {
+        let mut prompter = Scripted::new(vec![
+            Some(json!("web")),
+            Some(json!("Add element")),
+            Some(json!(80)),
+            // Take back the port and enter a different one.
+            None,
+            Some(json!(8080)),
+            Some(json!("Done")),
+        ]);
+        let handle = tokio::spawn(async move { Config::parse_to_obj_async(&mut prompter).await });
+        assert_eq!(
+            handle.await.unwrap().unwrap(),
+            Config {
+                name: "web".to_string(),
+                ports: vec![8080],
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_answer() {
+        #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+        struct Tags {
+            tags: std::collections::BTreeSet<String>,
+        }
+
+        let mut prompter = Scripted::new(vec![
+            Some(json!("Add element")),
+            Some(json!("a")),
+            Some(json!("Add element")),
+            Some(json!("a")),
+            Some(json!("b")),
+            Some(json!("Done")),
+        ]);
+        let tags = Tags::parse_to_obj_async(&mut prompter).await.unwrap();
+        assert_eq!(tags.tags, ["a", "b"].map(String::from).into());
+        assert!(prompter.helps[4].starts_with("\"a\" is already in the list, please try again"));
+
+        let options = ParseOptions::new().with_validator("/tags/*", |tag, _| match tag == "c" {
+            true => Err("c is reserved".to_string()),
+            false => Ok(()),
+        });
+        let mut prompter = Scripted::new(vec![
+            Some(json!("Add element")),
+            Some(json!("c")),
+            Some(json!("d")),
+            Some(json!("Done")),
+        ]);
+        let tags = Tags::parse_to_obj_async_with(&options, &mut prompter)
+            .await
+            .unwrap();
+        assert_eq!(tags.tags, ["d"].map(String::from).into());
+    }
+
+    #[tokio::test]
+    async fn test_callbacks_off_the_runtime() {
+        let runtime_thread = std::thread::current().id();
+        let options = ParseOptions::new().with_validator("/name", move |_, _| {
+            match std::thread::current().id() == runtime_thread {
+                true => Err("validator ran on the runtime thread".to_string()),
+                false => Ok(()),
+            }
+        });
+        let mut prompter = Scripted::new(vec![
+            Some(json!("web")),
+            Some(json!("Add element")),
+            Some(json!(80)),
+            Some(json!("Done")),
+        ]);
+        let config = Config::parse_to_obj_async_with(&options, &mut prompter)
+            .await
+            .unwrap();
+        assert_eq!(config.name, "web");
+    }
+}
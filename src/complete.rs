@@ -0,0 +1,184 @@
+use std::path::Path;
+
+use inquire::{autocompletion::Replacement, Autocomplete, CustomUserError};
+
+use crate::prompt::FilePath;
+
+/// Completes paths from the local filesystem, suggesting only the
+/// directories and files that `file_path` allows.
+#[derive(Debug, Clone)]
+pub(crate) struct PathCompleter {
+    pub(crate) file_path: FilePath,
+}
+
+impl Autocomplete for PathCompleter {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+        // Everything up to the last separator is the directory to list, and
+        // the rest is the start of a name in it.
+        let split = input
+            .rfind(['/', std::path::MAIN_SEPARATOR])
+            .map_or(0, |i| i + 1);
+        let (directory, prefix) = input.split_at(split);
+        let Ok(entries) = std::fs::read_dir(if directory.is_empty() { "." } else { directory })
+        else {
+            return Ok(Vec::new());
+        };
+        let mut suggestions = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let is_dir = entry.path().is_dir();
+                let hidden = name.starts_with('.') && !prefix.starts_with('.');
+                if !name.starts_with(prefix) || hidden {
+                    return None;
+                }
+                if !is_dir
+                    && (self.file_path.directory
+                        || !self.file_path.allows_extension(Path::new(&name)))
+                {
+                    return None;
+                }
+                Some(match is_dir {
+                    true => format!("{directory}{name}/"),
+                    false => format!("{directory}{name}"),
+                })
+            })
+            .collect::<Vec<_>>();
+        suggestions.sort();
+        Ok(suggestions)
+    }
+
+    fn get_completion(
+        &mut self,
+        input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, CustomUserError> {
+        if highlighted_suggestion.is_some() {
+            return Ok(highlighted_suggestion);
+        }
+        Ok(
+            common_prefix(&self.get_suggestions(input)?)
+                .filter(|prefix| prefix.len() > input.len()),
+        )
+    }
+}
+
+/// Suggests the `choices` that contain what has been typed so far.
+#[derive(Debug, Clone)]
+pub(crate) struct ListCompleter {
+    pub(crate) choices: Vec<String>,
+}
+
+impl Autocomplete for ListCompleter {
+    fn get_suggestions(&mut self, input: &str) -> Result<Vec<String>, CustomUserError> {
+        let input = input.to_lowercase();
+        Ok(self
+            .choices
+            .iter()
+            .filter(|choice| choice.to_lowercase().contains(&input))
+            .cloned()
+            .collect())
+    }
+
+    fn get_completion(
+        &mut self,
+        input: &str,
+        highlighted_suggestion: Option<String>,
+    ) -> Result<Replacement, CustomUserError> {
+        if highlighted_suggestion.is_some() {
+            return Ok(highlighted_suggestion);
+        }
+        let starting = self
+            .choices
+            .iter()
+            .filter(|choice| choice.starts_with(input))
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(common_prefix(&starting).filter(|prefix| prefix.len() > input.len()))
+    }
+}
+
+/// The longest string that every suggestion starts with.
+fn common_prefix(suggestions: &[String]) -> Option<String> {
+    let (first, rest) = suggestions.split_first()?;
+    let mut prefix = first.as_str();
+    for suggestion in rest {
+        while !suggestion.starts_with(prefix) {
+            let mut chars = prefix.chars();
+            chars.next_back();
+            prefix = chars.as_str();
+        }
+    }
+    Some(prefix.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use inquire::Autocomplete;
+
+    use super::{ListCompleter, PathCompleter};
+    use crate::prompt::FilePath;
+
+    #[test]
+    fn test_path_completer() {
+        let directory = std::env::temp_dir().join("interactive-parse-test-complete");
+        std::fs::create_dir_all(directory.join("configs")).unwrap();
+        std::fs::write(directory.join("config.toml"), "").unwrap();
+        std::fs::write(directory.join("config.json"), "").unwrap();
+        let input = format!("{}/conf", directory.display());
+
+        let mut completer = PathCompleter {
+            file_path: FilePath {
+                extensions: vec!["toml".to_string()],
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            completer.get_suggestions(&input).unwrap(),
+            vec![
+                format!("{}/config.toml", directory.display()),
+                format!("{}/configs/", directory.display()),
+            ]
+        );
+        assert_eq!(
+            completer.get_completion(&input, None).unwrap(),
+            Some(format!("{}/config", directory.display()))
+        );
+
+        completer.file_path.directory = true;
+        assert_eq!(
+            completer.get_suggestions(&input).unwrap(),
+            vec![format!("{}/configs/", directory.display())]
+        );
+        assert_eq!(
+            completer.file_path.check(&format!("{input}ig.toml")),
+            Err(format!("{input}ig.toml is not a directory"))
+        );
+        completer.file_path.must_exist = true;
+        assert_eq!(
+            completer.file_path.check(&format!("{input}ig")),
+            Err(format!("{input}ig does not exist"))
+        );
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_list_completer() {
+        let mut completer = ListCompleter {
+            choices: vec![
+                "eu-west-1".to_string(),
+                "eu-west-2".to_string(),
+                "us-east-1".to_string(),
+            ],
+        };
+        assert_eq!(
+            completer.get_suggestions("WEST").unwrap(),
+            vec!["eu-west-1", "eu-west-2"]
+        );
+        assert_eq!(
+            completer.get_completion("eu", None).unwrap(),
+            Some("eu-west-".to_string())
+        );
+        assert_eq!(completer.get_completion("us-east-1", None).unwrap(), None);
+    }
+}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, SecondsFormat, TimeDelta};
+
+/// Reads `input` as a string of the given schema `format`, allowing
+/// shortcuts relative to now such as `now`, `tomorrow` or `+1d`, and writes
+/// it the way serde expects. Returns `None` for formats other than `date`,
+/// `date-time` and `time`.
+pub(crate) fn parse(format: &str, input: &str) -> Option<Result<String, String>> {
+    parse_at(format, input, Local::now())
+}
+
+fn parse_at(format: &str, input: &str, now: DateTime<Local>) -> Option<Result<String, String>> {
+    let input = input.trim();
+    let result = match (format, shortcut(input, now)) {
+        ("date", Some(date_time)) => Ok(date_time.format("%Y-%m-%d").to_string()),
+        ("date-time", Some(date_time)) => Ok(date_time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        ("time", Some(date_time)) => Ok(date_time.format("%H:%M:%S").to_string()),
+        // Seconds are easy to leave out when typing a time.
+        ("time", None) if NaiveTime::parse_from_str(input, "%H:%M").is_ok() => {
+            Ok(format!("{input}:00"))
+        }
+        ("date" | "date-time" | "time", None) => check(format, input).map(|()| input.to_string()),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// Checks that `input` is a string of the given schema `format`, without
+/// allowing shortcuts.
+pub(crate) fn check(format: &str, input: &str) -> Result<(), String> {
+    let valid = match format {
+        "date" => NaiveDate::parse_from_str(input, "%Y-%m-%d").is_ok(),
+        "date-time" => DateTime::parse_from_rfc3339(input).is_ok(),
+        "time" => {
+            let time = input.strip_suffix(['Z', 'z']).unwrap_or(input);
+            NaiveTime::parse_from_str(time, "%H:%M:%S%.f").is_ok()
+                || NaiveTime::parse_from_str(time, "%H:%M:%S%.f%:z").is_ok()
+        }
+        _ => true,
+    };
+    match (valid, format) {
+        (true, _) => Ok(()),
+        (false, "date") => Err("must be a date like 2024-12-31, or today, +1d, -1w".to_string()),
+        (false, "date-time") => Err(
+            "must be an RFC 3339 date and time like 2024-12-31T09:30:00Z, or now, +1h, -2d"
+                .to_string(),
+        ),
+        (false, _) => Err("must be a time like 09:30:00, or now, +1h, -30m".to_string()),
+    }
+}
+
+/// A time relative to `now`: `now`, `today`, `tomorrow`, `yesterday`, or a
+/// signed number of seconds, minutes, hours, days or weeks like `+1d`.
+fn shortcut(input: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let input = input.to_lowercase();
+    let (sign, offset) = match input.as_str() {
+        "now" | "today" => return Some(now),
+        "tomorrow" => (1, "1d"),
+        "yesterday" => (-1, "1d"),
+        input => match input.split_at_checked(1)? {
+            ("+", offset) => (1, offset),
+            ("-", offset) => (-1, offset),
+            _ => return None,
+        },
+    };
+    let (amount, unit) = offset.split_at_checked(offset.len().checked_sub(1)?)?;
+    let amount = amount.trim().parse::<i64>().ok()? * sign;
+    let delta = match unit {
+        "s" => TimeDelta::try_seconds(amount),
+        "m" => TimeDelta::try_minutes(amount),
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => None,
+    }?;
+    now.checked_add_signed(delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, SecondsFormat, TimeDelta, TimeZone};
+
+    use super::parse_at;
+
+    #[test]
+    fn test_parse() {
+        let now = Local.with_ymd_and_hms(2024, 2, 28, 9, 30, 0).unwrap();
+        let parse = |format, input| parse_at(format, input, now).unwrap();
+        assert_eq!(parse("date", "today"), Ok("2024-02-28".to_string()));
+        assert_eq!(parse("date", "+2d"), Ok("2024-03-01".to_string()));
+        assert_eq!(parse("date", "2024-01-31"), Ok("2024-01-31".to_string()));
+        assert!(parse("date", "2024-02-30").is_err());
+        assert_eq!(parse("time", "-30m"), Ok("09:00:00".to_string()));
+        assert_eq!(parse("time", "17:05"), Ok("17:05:00".to_string()));
+        assert_eq!(parse("time", "17:05:00Z"), Ok("17:05:00Z".to_string()));
+        assert_eq!(
+            parse("date-time", "2024-01-31T09:30:00+01:00"),
+            Ok("2024-01-31T09:30:00+01:00".to_string())
+        );
+        assert_eq!(
+            parse("date-time", "+1W"),
+            Ok((now + TimeDelta::weeks(1)).to_rfc3339_opts(SecondsFormat::Secs, true))
+        );
+        assert!(parse("date-time", "2024-01-31").is_err());
+        assert_eq!(parse_at("email", "now", now), None);
+    }
+}
@@ -1,7 +1,11 @@
 use inquire::InquireError;
+use schemars::schema::RootSchema;
+use serde::de::DeserializeOwned;
 use serde_json::Value;
 use thiserror::Error;
 
+use crate::secret;
+
 pub type SchemaResult<T> = core::result::Result<T, SchemaError>;
 
 #[derive(Error, Debug)]
@@ -9,15 +13,66 @@ pub enum SchemaError {
     #[error(transparent)]
     Inquire(#[from] InquireError),
 
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     #[error("{0}")]
     Generic(String),
 
     #[error("Undo depth: {depth}")]
     Undo { depth: u16 },
 
-    #[error("User has exited the prompt")]
-    Exit,
+    /// The user cancelled the prompt at `path`. `partial` holds everything
+    /// that was entered before that point.
+    #[error("User has exited the prompt at \"{path}\"")]
+    Exit { partial: Value, path: String },
+
+    /// A detached parse reached a question that has not been answered yet.
+    /// This is handled internally and never returned from `parse_to_*`.
+    #[error("The parse is waiting for an answer")]
+    Unanswered,
+
+    /// An answer given to a detached parse does not fit its question.
+    #[error("Invalid answer at \"{path}\": {message}")]
+    InvalidAnswer { path: String, message: String },
+
+    /// A custom prompt is registered for a name that is neither a
+    /// definition nor a title in the schema, so it would never be asked.
+    #[error("No definition or title in the schema is named \"{name}\", so its prompt would never be used")]
+    UnusedPrompt { name: String },
+
+    /// Choices are registered for a definition that isn't a string, so they
+    /// couldn't be picked from.
+    #[error(
+        "The definition \"{name}\" is not a string, so it can't be picked from a list of choices"
+    )]
+    ChoicesNotString { name: String },
+
+    /// A validated parse asks for the fields that fail validation again on
+    /// the terminal, which the `frontend` picked in the options can't do.
+    #[error("Validated parses ask again on the terminal, so they can't use the {frontend}")]
+    TerminalOnly { frontend: &'static str },
+
+    /// The `frontend` picked in the options can't apply another `option`
+    /// that was set, which would otherwise be silently ignored.
+    #[error("The {frontend} doesn't support {option}")]
+    UnsupportedOption {
+        frontend: &'static str,
+        option: &'static str,
+    },
+
+    /// The web form was asked to serve on an address other machines can
+    /// reach.
+    #[error("The web form only serves on loopback addresses, not {addr}")]
+    NotLoopback { addr: std::net::SocketAddr },
+
+    /// A value read back from an answer file still has a secret masked, which
+    /// has to be filled in by hand first.
+    #[error("The secret at \"{path}\" is still masked, fill it in before reading the file")]
+    MaskedSecret { path: String },
 
+    /// The parsed value could not be deserialized. Secrets in `value` are
+    /// replaced by `********`.
     #[error(
         "interactive-parse generated this json object: {}\n{}",
         serde_json::to_string_pretty(&value).unwrap(),
@@ -40,3 +95,14 @@ pub enum SchemaError {
     )]
     Unimplemented,
 }
+
+/// Deserializes a value parsed against `root_schema`.
+pub(crate) fn from_value<T: DeserializeOwned>(
+    root_schema: &RootSchema,
+    value: Value,
+) -> SchemaResult<T> {
+    serde_json::from_value::<T>(value.clone()).map_err(|serde_error| SchemaError::Serde {
+        value: secret::redact(&root_schema.definitions, &root_schema.schema, &value),
+        serde_error,
+    })
+}
@@ -0,0 +1,841 @@
+use std::io::stdout;
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Terminal,
+};
+use schemars::schema::{RootSchema, Schema, SchemaObject};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::{SchemaError, SchemaResult},
+    get_root_title,
+    prompt::{Question, QuestionKind},
+    secret,
+    shape::{description, shape, Shape, Variant},
+    validate::validate_value,
+};
+
+/// A value being edited in the form.
+enum Node {
+    Leaf {
+        question: Box<Question>,
+        input: String,
+        error: Option<String>,
+    },
+    Object {
+        description: Option<String>,
+        fields: Vec<(String, Node)>,
+    },
+    Optional {
+        schema: SchemaObject,
+        inner: Option<Box<Node>>,
+    },
+    Enum {
+        description: Option<String>,
+        /// Each variant's name, and what it holds.
+        variants: Vec<(String, Variant)>,
+        selected: usize,
+        inner: Option<Box<Node>>,
+    },
+    Array {
+        description: Option<String>,
+        /// The schema of each element, or `None` for tuples.
+        item: Option<SchemaObject>,
+        elements: Vec<Node>,
+        min_items: u32,
+        max_items: Option<u32>,
+    },
+}
+
+/// A line of the form, pointing at a node by the index of each child on the
+/// way down from the root.
+struct Row {
+    depth: usize,
+    label: String,
+    path: Vec<usize>,
+}
+
+struct Form<'a> {
+    definitions: &'a schemars::Map<String, Schema>,
+    schema: &'a SchemaObject,
+    root: Node,
+    title: String,
+    selected: usize,
+    /// Why the last submitted value was rejected as a whole.
+    error: Option<String>,
+}
+
+impl<'a> Form<'a> {
+    fn new(root_schema: &'a RootSchema) -> SchemaResult<Self> {
+        let definitions = &root_schema.definitions;
+        Ok(Self {
+            definitions,
+            schema: &root_schema.schema,
+            root: build(definitions, &root_schema.schema, "value")?,
+            title: get_root_title(root_schema).unwrap_or_default(),
+            selected: 0,
+            error: None,
+        })
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        // The fields of a root object are shown without a heading.
+        let inline = matches!(self.root, Node::Object { .. });
+        push_rows(&self.root, "value", 0, &mut Vec::new(), inline, &mut rows);
+        rows
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.rows().into_iter().nth(self.selected)
+    }
+
+    fn node_mut(&mut self, path: &[usize]) -> &mut Node {
+        path.iter()
+            .fold(&mut self.root, |node, i| node.child_mut(*i))
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> SchemaResult<()> {
+        let row_count = self.rows().len();
+        let Some(row) = self.selected_row() else {
+            return Ok(());
+        };
+        let definitions = self.definitions;
+        match key.code {
+            KeyCode::Up | KeyCode::BackTab => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Tab | KeyCode::Enter => {
+                self.selected = (self.selected + 1).min(row_count.saturating_sub(1))
+            }
+            KeyCode::Delete => {
+                // Removes the selected array element, unless the array would
+                // become too short.
+                if let Some((index, parent)) = row.path.split_last() {
+                    if let Node::Array {
+                        item: Some(_),
+                        elements,
+                        min_items,
+                        ..
+                    } = self.node_mut(parent)
+                    {
+                        if elements.len() > *min_items as usize {
+                            elements.remove(*index);
+                        }
+                    }
+                }
+                self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+            }
+            code => match self.node_mut(&row.path) {
+                Node::Leaf {
+                    question, input, ..
+                } if question.kind == QuestionKind::Boolean => {
+                    if matches!(code, KeyCode::Char(' ') | KeyCode::Left | KeyCode::Right) {
+                        *input = (input != "true").to_string();
+                    }
+                }
+                Node::Leaf { input, .. } => match code {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    _ => {}
+                },
+                Node::Optional { schema, inner } if code == KeyCode::Char(' ') => {
+                    *inner = match inner {
+                        Some(_) => None,
+                        None => Some(Box::new(build(definitions, schema, &row.label)?)),
+                    };
+                }
+                Node::Enum {
+                    variants,
+                    selected,
+                    inner,
+                    ..
+                } => {
+                    let len = variants.len();
+                    *selected = match code {
+                        KeyCode::Left => (*selected + len - 1) % len,
+                        KeyCode::Right | KeyCode::Char(' ') => (*selected + 1) % len,
+                        _ => return Ok(()),
+                    };
+                    *inner = match &variants[*selected] {
+                        (name, Variant::Contents(schema)) => {
+                            Some(Box::new(build(definitions, schema, name)?))
+                        }
+                        (_, Variant::Unit(_)) => None,
+                    };
+                }
+                Node::Array {
+                    item: Some(item),
+                    elements,
+                    max_items,
+                    ..
+                } => {
+                    let full = max_items.is_some_and(|max| elements.len() >= max as usize);
+                    if code == KeyCode::Char('+') && !full {
+                        elements.push(build(definitions, item, &row.label)?);
+                    }
+                }
+                _ => {}
+            },
+        }
+        Ok(())
+    }
+
+    fn draw(&self, frame: &mut ratatui::Frame) {
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(4)]).areas(frame.area());
+        let rows = self.rows();
+        let items = rows
+            .iter()
+            .map(|row| {
+                let node = self.node(&row.path);
+                let mut spans = vec![
+                    Span::raw("  ".repeat(row.depth)),
+                    Span::styled(
+                        format!("{}: ", row.label),
+                        Style::new().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(node.display()),
+                ];
+                if let Node::Leaf {
+                    error: Some(error), ..
+                } = node
+                {
+                    spans.push(Span::styled(
+                        format!("  {error}"),
+                        Style::new().fg(Color::Red),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect::<Vec<_>>();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(self.title.as_str()),
+            )
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut list_state = ListState::default().with_selected(Some(self.selected));
+        frame.render_stateful_widget(list, list_area, &mut list_state);
+
+        let description = rows
+            .get(self.selected)
+            .and_then(|row| self.node(&row.path).description())
+            .unwrap_or_default();
+        let help = Paragraph::new(vec![
+            match &self.error {
+                Some(error) => Line::from(error.as_str()).style(Style::new().fg(Color::Red)),
+                None => Line::from(description),
+            },
+            Line::from(
+                "↑/↓ move  type to edit  space/←/→ toggle or change variant  \
+                 + add element  del remove element  ctrl-s submit  esc cancel",
+            )
+            .style(Style::new().fg(Color::DarkGray)),
+        ])
+        .wrap(Wrap { trim: true })
+        .block(Block::default().borders(Borders::TOP));
+        frame.render_widget(help, help_area);
+    }
+
+    fn node(&self, path: &[usize]) -> &Node {
+        path.iter().fold(&self.root, |node, i| node.child(*i))
+    }
+
+    /// The finished value, or `None` after pointing out what is wrong with
+    /// it. Besides each field, the value as a whole is checked against the
+    /// schema, which catches too many elements or repeated ones in a set.
+    fn submit(&mut self) -> Option<Value> {
+        let Some(value) = self.root.value() else {
+            self.error = None;
+            self.select_first_error();
+            return None;
+        };
+        let Err(message) = validate_value(self.definitions, self.schema, &value) else {
+            self.error = None;
+            return Some(value);
+        };
+        // Errors start with the json pointer of the offending value.
+        if let Some(position) = self
+            .rows()
+            .iter()
+            .rposition(|row| message.starts_with(&format!("{}: ", pointer(self, &row.path))))
+        {
+            self.selected = position;
+        }
+        self.error = Some(message);
+        None
+    }
+
+    /// Focuses the first row with an error.
+    fn select_first_error(&mut self) {
+        if let Some(position) = self
+            .rows()
+            .iter()
+            .position(|row| matches!(self.node(&row.path), Node::Leaf { error: Some(_), .. }))
+        {
+            self.selected = position;
+        }
+    }
+}
+
+impl Node {
+    fn child(&self, i: usize) -> &Node {
+        match self {
+            Node::Object { fields, .. } => &fields[i].1,
+            Node::Optional {
+                inner: Some(inner), ..
+            }
+            | Node::Enum {
+                inner: Some(inner), ..
+            } => inner,
+            Node::Array { elements, .. } => &elements[i],
+            _ => unreachable!("row path points past a leaf"),
+        }
+    }
+
+    fn child_mut(&mut self, i: usize) -> &mut Node {
+        match self {
+            Node::Object { fields, .. } => &mut fields[i].1,
+            Node::Optional {
+                inner: Some(inner), ..
+            }
+            | Node::Enum {
+                inner: Some(inner), ..
+            } => inner,
+            Node::Array { elements, .. } => &mut elements[i],
+            _ => unreachable!("row path points past a leaf"),
+        }
+    }
+
+    fn children(&self) -> Vec<(String, &Node)> {
+        match self {
+            Node::Object { fields, .. } => fields
+                .iter()
+                .map(|(name, node)| (name.clone(), node))
+                .collect(),
+            Node::Optional {
+                inner: Some(inner), ..
+            } => vec![("value".to_string(), inner)],
+            Node::Enum {
+                variants,
+                selected,
+                inner: Some(inner),
+                ..
+            } => vec![(variants[*selected].0.clone(), inner)],
+            Node::Array { elements, .. } => elements
+                .iter()
+                .enumerate()
+                .map(|(i, node)| (format!("[{i}]"), node))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn display(&self) -> String {
+        match self {
+            Node::Leaf {
+                question, input, ..
+            } if question.secret => "*".repeat(input.chars().count()),
+            Node::Leaf { input, .. } => input.clone(),
+            Node::Object { .. } => String::new(),
+            Node::Optional { inner, .. } => match inner {
+                Some(_) => "[x]".to_string(),
+                None => "[ ]".to_string(),
+            },
+            Node::Enum {
+                variants, selected, ..
+            } => format!("< {} >", variants[*selected].0),
+            Node::Array { elements, .. } => format!("{} elements", elements.len()),
+        }
+    }
+
+    fn description(&self) -> Option<String> {
+        match self {
+            Node::Leaf { question, .. } => question.description.clone(),
+            Node::Object { description, .. }
+            | Node::Enum { description, .. }
+            | Node::Array { description, .. } => description.clone(),
+            Node::Optional { schema, .. } => description(schema),
+        }
+    }
+
+    /// Builds the value, recording an error on every leaf that fails
+    /// validation. Returns `None` if any leaf did.
+    fn value(&mut self) -> Option<Value> {
+        match self {
+            Node::Leaf {
+                question,
+                input,
+                error,
+            } => {
+                let result = question.parse(input);
+                *error = result.as_ref().err().cloned();
+                result.ok()
+            }
+            Node::Object { fields, .. } => {
+                let mut map = Map::new();
+                let mut valid = true;
+                for (name, node) in fields {
+                    match node.value() {
+                        Some(value) => {
+                            map.insert(name.clone(), value);
+                        }
+                        None => valid = false,
+                    }
+                }
+                valid.then_some(Value::Object(map))
+            }
+            Node::Optional { inner, .. } => match inner {
+                Some(inner) => inner.value(),
+                None => Some(Value::Null),
+            },
+            Node::Enum {
+                variants,
+                selected,
+                inner,
+                ..
+            } => {
+                let (name, variant) = &variants[*selected];
+                match (inner, variant) {
+                    (Some(inner), _) => Some(json!({ name.clone(): inner.value()? })),
+                    (None, Variant::Unit(value)) => Some(value.clone()),
+                    (None, Variant::Contents(_)) => None,
+                }
+            }
+            Node::Array { elements, .. } => {
+                let values = elements.iter_mut().map(Node::value).collect::<Vec<_>>();
+                values
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .map(Value::Array)
+            }
+        }
+    }
+
+    /// The value as far as it has been filled in, with `null` in place of
+    /// anything invalid.
+    fn partial(&self) -> Value {
+        match self {
+            Node::Leaf {
+                question, input, ..
+            } => question.parse(input).unwrap_or_default(),
+            Node::Object { fields, .. } => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, node)| (name.clone(), node.partial()))
+                    .collect(),
+            ),
+            Node::Optional { inner, .. } => inner
+                .as_ref()
+                .map(|inner| inner.partial())
+                .unwrap_or_default(),
+            Node::Enum {
+                variants,
+                selected,
+                inner,
+                ..
+            } => {
+                let (name, variant) = &variants[*selected];
+                match (inner, variant) {
+                    (Some(inner), _) => json!({ name.clone(): inner.partial() }),
+                    (None, Variant::Unit(value)) => value.clone(),
+                    (None, Variant::Contents(_)) => Value::Null,
+                }
+            }
+            Node::Array { elements, .. } => {
+                Value::Array(elements.iter().map(Node::partial).collect())
+            }
+        }
+    }
+}
+
+fn push_rows(
+    node: &Node,
+    label: &str,
+    depth: usize,
+    path: &mut Vec<usize>,
+    inline: bool,
+    rows: &mut Vec<Row>,
+) {
+    let child_depth = if inline {
+        depth
+    } else {
+        rows.push(Row {
+            depth,
+            label: label.to_string(),
+            path: path.clone(),
+        });
+        depth + 1
+    };
+    for (i, (label, child)) in node.children().into_iter().enumerate() {
+        path.push(i);
+        // The contents of optionals and variants are shown directly under
+        // them rather than under a heading of their own.
+        let inline = matches!(child, Node::Object { .. })
+            && matches!(node, Node::Optional { .. } | Node::Enum { .. });
+        push_rows(child, &label, child_depth, path, inline, rows);
+        path.pop();
+    }
+}
+
+fn build(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+    name: &str,
+) -> SchemaResult<Node> {
+    let (schema, shape) = shape(definitions, schema)?;
+    let description = description(&schema);
+    Ok(match shape {
+        Shape::Leaf(kind) => {
+            let question = Question::new(kind, name, "").with_schema(&schema);
+            let input = match &question.default {
+                Some(Value::String(default)) => default.clone(),
+                Some(default) => default.to_string(),
+                None if kind == QuestionKind::Boolean => "false".to_string(),
+                None => String::new(),
+            };
+            Node::Leaf {
+                question: Box::new(question),
+                input,
+                error: None,
+            }
+        }
+        Shape::Object(properties) => Node::Object {
+            description,
+            fields: properties
+                .iter()
+                .map(|(name, property)| Ok((name.clone(), build(definitions, property, name)?)))
+                .collect::<SchemaResult<_>>()?,
+        },
+        Shape::Optional(schema) => Node::Optional {
+            schema,
+            inner: None,
+        },
+        Shape::Enum(variants) => {
+            let inner = match &variants[0] {
+                (name, Variant::Contents(schema)) => {
+                    Some(Box::new(build(definitions, schema, name)?))
+                }
+                (_, Variant::Unit(_)) => None,
+            };
+            Node::Enum {
+                description,
+                variants,
+                selected: 0,
+                inner,
+            }
+        }
+        Shape::Array {
+            item,
+            min_items,
+            max_items,
+        } => Node::Array {
+            description,
+            elements: (0..min_items)
+                .map(|i| build(definitions, &item, &format!("[{i}]")))
+                .collect::<SchemaResult<_>>()?,
+            item: Some(item),
+            min_items,
+            max_items,
+        },
+        Shape::Tuple(items) => Node::Array {
+            description,
+            item: None,
+            elements: items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| build(definitions, item, &format!("[{i}]")))
+                .collect::<SchemaResult<_>>()?,
+            min_items: 0,
+            max_items: None,
+        },
+    })
+}
+
+/// Restores the terminal when the form closes, even on error.
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(stdout(), LeaveAlternateScreen);
+    }
+}
+
+/// Shows every field of the schema at once in a full-screen form.
+pub(crate) fn parse_form(root_schema: &RootSchema) -> SchemaResult<Value> {
+    let mut form = Form::new(root_schema)?;
+
+    enable_raw_mode()?;
+    execute!(stdout(), EnterAlternateScreen)?;
+    let _guard = TerminalGuard;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+    loop {
+        terminal.draw(|frame| form.draw(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => {
+                let path = form
+                    .selected_row()
+                    .map(|row| pointer(&form, &row.path))
+                    .unwrap_or_default();
+                return Err(SchemaError::Exit {
+                    partial: secret::redact(
+                        &root_schema.definitions,
+                        &root_schema.schema,
+                        &form.root.partial(),
+                    ),
+                    path,
+                });
+            }
+            KeyCode::Char('s') if ctrl => {
+                if let Some(value) = form.submit() {
+                    return Ok(value);
+                }
+            }
+            KeyCode::Char('c') if ctrl => {
+                return Err(SchemaError::Exit {
+                    partial: secret::redact(
+                        &root_schema.definitions,
+                        &root_schema.schema,
+                        &form.root.partial(),
+                    ),
+                    path: String::new(),
+                })
+            }
+            _ => form.handle_key(key)?,
+        }
+    }
+}
+
+/// The json pointer of the node at `path`.
+fn pointer(form: &Form, path: &[usize]) -> String {
+    let mut node = &form.root;
+    let mut pointer = String::new();
+    for i in path {
+        match node {
+            Node::Object { fields, .. } => {
+                pointer.push_str(&format!("/{}", segment(&fields[*i].0)))
+            }
+            Node::Enum {
+                variants, selected, ..
+            } => pointer.push_str(&format!("/{}", segment(&variants[*selected].0))),
+            Node::Array { .. } => pointer.push_str(&format!("/{i}")),
+            _ => {}
+        }
+        node = node.child(*i);
+    }
+    pointer
+}
+
+/// `name` escaped for use as a json pointer segment.
+fn segment(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, Terminal};
+    use schemars::{schema_for, JsonSchema};
+    use serde_json::json;
+
+    use super::Form;
+    use crate::{error::SchemaError, ParseOptions};
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    enum Mode {
+        Fast,
+        Slow { delay: u8 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Job {
+        /// The name of the job.
+        name: String,
+        mode: Mode,
+        retries: Option<u32>,
+        tags: Vec<String>,
+    }
+
+    fn press(form: &mut Form, code: KeyCode) {
+        form.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+            .unwrap();
+    }
+
+    fn type_str(form: &mut Form, string: &str) {
+        for c in string.chars() {
+            press(form, KeyCode::Char(c));
+        }
+    }
+
+    #[test]
+    fn test_form() {
+        let root_schema = schema_for!(Job);
+        let mut form = Form::new(&root_schema).unwrap();
+        let labels = |form: &Form| {
+            form.rows()
+                .into_iter()
+                .map(|row| row.label)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(labels(&form), vec!["mode", "name", "retries", "tags"]);
+
+        // Switch to the struct variant, which expands in place.
+        press(&mut form, KeyCode::Right);
+        assert_eq!(
+            labels(&form),
+            vec!["mode", "delay", "name", "retries", "tags"]
+        );
+        press(&mut form, KeyCode::Down);
+        type_str(&mut form, "300");
+        press(&mut form, KeyCode::Down);
+        type_str(&mut form, "build");
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Char(' '));
+        press(&mut form, KeyCode::Down);
+        type_str(&mut form, "3");
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Char('+'));
+        press(&mut form, KeyCode::Down);
+        type_str(&mut form, "nightly");
+
+        // The delay does not fit in a u8.
+        assert_eq!(form.root.value(), None);
+        form.select_first_error();
+        assert_eq!(form.selected, 1);
+        press(&mut form, KeyCode::Backspace);
+        press(&mut form, KeyCode::Backspace);
+
+        assert_eq!(
+            form.root.value(),
+            Some(json!({
+                "mode": { "Slow": { "delay": 3 } },
+                "name": "build",
+                "retries": 3,
+                "tags": ["nightly"],
+            }))
+        );
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+        terminal.draw(|frame| form.draw(frame)).unwrap();
+        let screen = format!("{:?}", terminal.backend().buffer());
+        assert!(screen.contains("delay: 3"));
+    }
+
+    #[test]
+    fn test_form_arrays() {
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        struct Tags {
+            #[schemars(length(min = 1))]
+            tags: std::collections::BTreeSet<String>,
+        }
+
+        let root_schema = schema_for!(Tags);
+        let mut form = Form::new(&root_schema).unwrap();
+        // The set starts with the one element it needs.
+        assert_eq!(form.rows().len(), 2);
+        press(&mut form, KeyCode::Down);
+        type_str(&mut form, "a");
+        press(&mut form, KeyCode::Up);
+        press(&mut form, KeyCode::Char('+'));
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Down);
+        type_str(&mut form, "a");
+        assert_eq!(form.rows().len(), 3);
+
+        // The set has a repeated element, which is pointed out on submit.
+        assert_eq!(form.submit(), None);
+        assert!(form.error.as_ref().unwrap().starts_with("/tags: "));
+        assert_eq!(form.selected, 0);
+
+        // Deleting the last row keeps a row selected.
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Delete);
+        assert_eq!(form.rows().len(), 2);
+        assert_eq!(form.selected, 1);
+        assert_eq!(form.submit(), Some(json!({ "tags": ["a"] })));
+        assert_eq!(form.error, None);
+
+        // The set needs at least one element.
+        press(&mut form, KeyCode::Delete);
+        assert_eq!(form.rows().len(), 2);
+        press(&mut form, KeyCode::Up);
+        assert_eq!(form.selected, 0);
+    }
+
+    #[test]
+    fn test_escaped_names() {
+        fn level(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+            serde_json::from_value(json!({ "enum": [1, 2] })).unwrap()
+        }
+
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        struct Limits {
+            #[serde(rename = "in/out")]
+            in_out: std::collections::BTreeSet<String>,
+            #[schemars(schema_with = "level")]
+            level: u8,
+        }
+
+        let root_schema = schema_for!(Limits);
+        let mut form = Form::new(&root_schema).unwrap();
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Right);
+        press(&mut form, KeyCode::Up);
+        press(&mut form, KeyCode::Char('+'));
+        press(&mut form, KeyCode::Char('+'));
+        press(&mut form, KeyCode::Down);
+        type_str(&mut form, "x");
+        press(&mut form, KeyCode::Down);
+        type_str(&mut form, "x");
+
+        // The error is found under the escaped field name.
+        assert_eq!(form.submit(), None);
+        assert!(form.error.as_ref().unwrap().starts_with("/in~1out: "));
+        assert_eq!(form.selected, 0);
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Down);
+        press(&mut form, KeyCode::Delete);
+        // The enum keeps its values as numbers.
+        assert_eq!(form.submit(), Some(json!({ "in/out": ["x"], "level": 2 })));
+    }
+
+    #[test]
+    fn test_unsupported_options() {
+        let options = ParseOptions::new()
+            .with_form()
+            .with_validator("/name", |_, _| Ok(()));
+        assert!(matches!(
+            crate::parse_root(schema_for!(Job), &options),
+            Err(SchemaError::UnsupportedOption {
+                frontend: "form",
+                option: "validators"
+            })
+        ));
+    }
+}
@@ -1,47 +1,150 @@
-use std::cell::Cell;
+use std::sync::Arc;
 
+use base64::prelude::{Engine, BASE64_STANDARD};
 use error::{SchemaError, SchemaResult};
-use inquire::{Confirm, CustomType, Select, Text};
+use inquire::Confirm;
 use log::debug;
 use schemars::schema::{
-    ArrayValidation, InstanceType, ObjectValidation, Schema, SchemaObject, SingleOrVec,
+    ArrayValidation, InstanceType, ObjectValidation, RootSchema, Schema, SchemaObject, SingleOrVec,
     SubschemaValidation,
 };
 use serde_json::{json, Map, Value};
 use undo::clear_lines;
 
-use crate::undo::{RecurseIter, RecurseLoop, Undo};
+use crate::{
+    array::ArrayMenu,
+    options::{Callback, ChoiceProvider},
+    resume::SessionFile,
+    state::ParseState,
+    undo::{RecurseIter, RecurseLoop},
+};
 
+mod array;
+#[cfg(feature = "async")]
+pub mod async_prompt;
+mod complete;
+mod datetime;
 pub mod error;
+#[cfg(feature = "form")]
+mod form;
+pub mod options;
+pub mod prompt;
+pub mod replay;
+pub mod resume;
+#[cfg(feature = "validator")]
+mod revalidate;
+pub mod rpc;
+mod secret;
+pub mod session;
+mod shape;
+mod state;
 pub mod traits;
 pub mod undo;
-
+mod validate;
+#[cfg(feature = "web")]
+pub mod web;
+
+#[cfg(feature = "async")]
+pub use async_prompt::AsyncPrompter;
+pub use options::ParseOptions;
+pub use prompt::{ByteInput, Column, Constraints, FilePath, Question, QuestionKind};
+pub use session::{Answer, Session};
 pub use traits::*;
 
+pub(crate) fn parse_root(root_schema: RootSchema, options: &ParseOptions) -> SchemaResult<Value> {
+    options.check_prompts(&root_schema)?;
+    #[cfg(feature = "form")]
+    if options.form {
+        options.check_form_options("form")?;
+        return form::parse_form(&root_schema);
+    }
+    #[cfg(feature = "web")]
+    if let Some(addr) = options.web_form {
+        options.check_form_options("web form")?;
+        let web_form = web::WebForm::bind(root_schema, addr)?;
+        log::info!("serving the form at http://{}", web_form.local_addr()?);
+        return web_form.serve();
+    }
+    if options.json_rpc {
+        // Answers are kept by the client, which can start over with them.
+        if options.session_file.is_some() {
+            return Err(SchemaError::UnsupportedOption {
+                frontend: "json-rpc frontend",
+                option: "session files",
+            });
+        }
+        return rpc::serve(
+            Session::with_options(root_schema, options.clone())?,
+            std::io::stdin().lock(),
+            std::io::stdout().lock(),
+        );
+    }
+
+    options.check_choices(&root_schema)?;
+    let session_file = options
+        .session_file
+        .as_ref()
+        .map(|path| SessionFile::new(path, &root_schema));
+    let mut replay = Vec::new();
+    if let Some(answers) = session_file
+        .as_ref()
+        .map(SessionFile::load)
+        .transpose()?
+        .flatten()
+    {
+        if Confirm::new("Resume previous session?")
+            .with_default(true)
+            .with_help_message(format!("{} saved answers", answers.len()).as_str())
+            .prompt()?
+        {
+            replay = answers;
+        }
+    }
+
+    let state = ParseState::new(&root_schema, options, session_file.clone(), replay);
+    let value = parse_root_value(&state, &root_schema)?;
+    if let Some(session_file) = session_file {
+        session_file.remove()?;
+    }
+    Ok(value)
+}
+
 pub(crate) fn parse_schema(
-    definitions: &schemars::Map<String, Schema>,
+    state: &ParseState,
     title: Option<String>,
     name: String,
     schema: SchemaObject,
-    current_depth: &Cell<u16>,
 ) -> SchemaResult<Value> {
-    let depth_checkpoint = current_depth.get();
-    match parse_schema_inner(
-        definitions,
-        title.clone(),
-        name.clone(),
-        schema.clone(),
-        current_depth,
-    ) {
-        Ok(value) => Ok(value),
+    let depth_checkpoint = state.current_depth.get();
+    let parse = || {
+        state.with_secret(secret::is_secret(&schema), || {
+            parse_schema_inner(state, title.clone(), name.clone(), schema.clone())
+        })
+    };
+    let pointer = state.pointer();
+    let result = match options::at_path(&state.options.validators, &pointer) {
+        Some(validator) => {
+            let parent = pointer
+                .rfind('/')
+                .and_then(|i| state.partial().pointer(&pointer[..i]).cloned())
+                .unwrap_or(Value::Null);
+            state.check(parse, |value| validator(value, &parent))
+        }
+        None => parse(),
+    };
+    match result {
+        Ok(value) => {
+            state.complete(&value);
+            Ok(value)
+        }
         Err(SchemaError::Undo { depth }) => {
             if depth <= depth_checkpoint && depth_checkpoint != 0 {
                 debug!("forwarding error in parse schema, depth: {depth}, depth_checkpoint: {depth_checkpoint}");
                 Err(SchemaError::Undo { depth })
             } else {
-                current_depth.set(depth_checkpoint);
+                state.current_depth.set(depth_checkpoint);
                 clear_lines(depth - depth_checkpoint + 1);
-                parse_schema(definitions, title, name, schema, current_depth)
+                parse_schema(state, title, name, schema)
             }
         }
         Err(e) => Err(e),
@@ -49,80 +152,143 @@ pub(crate) fn parse_schema(
 }
 
 pub(crate) fn parse_schema_inner(
-    definitions: &schemars::Map<String, Schema>,
+    state: &ParseState,
     title: Option<String>,
     name: String,
     schema: SchemaObject,
-    current_depth: &Cell<u16>,
 ) -> SchemaResult<Value> {
     debug!("Entered parse_schema");
+    // Inlined schemas can only be recognised by their title.
+    if let Some(prompt) = schema
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.title.as_ref())
+        .and_then(|title| state.options.prompts.get(title))
+    {
+        return get_custom(state, prompt.0.clone(), &name, &schema);
+    }
     let description = get_description(&schema);
     debug!("description: {}", description);
+    if state.options.json_entry && is_compound(&schema) {
+        if let Some(value) = get_json(state, &title, &name, &description, &schema)? {
+            return Ok(value);
+        }
+    }
     match schema.instance_type.clone() {
-        Some(SingleOrVec::Single(instance_type)) => get_single_instance(
-            definitions,
-            schema.array,
-            schema.object,
-            schema.subschemas,
-            instance_type,
-            title,
-            name,
-            description,
-            current_depth,
-        ),
+        Some(SingleOrVec::Single(instance_type)) => {
+            get_single_instance(state, schema, instance_type, title, name, description)
+        }
         Some(SingleOrVec::Vec(vec)) => {
             // This usually represents an optional regular type
             let instance_type =
                 Box::new(vec.into_iter().find(|x| x != &InstanceType::Null).unwrap());
-            if Confirm::new("Add optional value?")
-                .with_help_message(format!("{}{}", get_title_str(&title), name).as_str())
-                .prompt_skippable()?
-                .undo(current_depth)?
-            {
-                get_single_instance(
-                    definitions,
-                    schema.array,
-                    schema.object,
-                    schema.subschemas,
-                    instance_type,
-                    title,
-                    name,
-                    description,
-                    current_depth,
-                )
+            if get_optional(state, &title, &name)? {
+                get_single_instance(state, schema, instance_type, title, name, description)
             } else {
                 Ok(Value::Null)
             }
         }
         None => {
+            let default = schema
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.default.clone());
             // This represents a referenced type
             if let Some(reference) = schema.reference {
                 let reference = reference.strip_prefix("#/definitions/").unwrap();
-                let schema = definitions.get(reference).unwrap();
+                let schema = state.definitions.get(reference).unwrap();
                 let schema = get_schema_object_ref(schema)?;
-                parse_schema(
-                    definitions,
-                    Some(reference.to_string()),
-                    name,
-                    schema.clone(),
-                    current_depth,
-                )
+                if let Some(prompt) = state.options.prompts.get(reference) {
+                    return get_custom(state, prompt.0.clone(), &name, schema);
+                }
+                if let Some(provider) = state.options.definition_choices.get(reference) {
+                    if let Some(value) = get_choice(
+                        state,
+                        schema,
+                        name.clone(),
+                        &description,
+                        provider.0.as_ref(),
+                    )? {
+                        return Ok(value);
+                    }
+                }
+                let schema = match &default {
+                    Some(default) => with_default(schema.clone(), default),
+                    None => schema.clone(),
+                };
+                parse_schema(state, Some(reference.to_string()), name, schema)
             }
             // Or it could be a subschema
             else {
-                get_subschema(
-                    definitions,
-                    title,
-                    name,
-                    schema.subschemas,
-                    description,
-                    current_depth,
-                )
+                get_subschema(state, title, name, schema.subschemas, description)
             }
         }
     }
 }
 
+/// Parses the value described by `root_schema`, then offers to edit the
+/// result as json if the options ask for it.
+pub(crate) fn parse_root_value(
+    state: &ParseState,
+    root_schema: &RootSchema,
+) -> SchemaResult<Value> {
+    let value = parse_schema(
+        state,
+        get_root_title(root_schema),
+        String::default(),
+        root_schema.schema.clone(),
+    )?;
+    if !state.options.final_edit {
+        return Ok(value);
+    }
+
+    let mut question = Question::new(
+        QuestionKind::String,
+        "Edit the result:",
+        "save and close the editor to finish, or empty the file to keep the result as it is",
+    );
+    question.widget = Some("textarea".to_string());
+    // Secrets stay hidden in the editor, and are put back unless replaced.
+    let redacted = secret::redact(&root_schema.definitions, &root_schema.schema, &value);
+    question.default = Some(Value::String(
+        serde_json::to_string_pretty(&redacted).unwrap_or_default(),
+    ));
+    question.json_schema = Some(Box::new(root_schema.clone()));
+    let (original, unredact_schema) = (value.clone(), root_schema.clone());
+    question.restore = Some(Callback(Arc::new(move |edited| {
+        secret::unredact(
+            &unredact_schema.definitions,
+            &unredact_schema.schema,
+            edited,
+            &original,
+        )
+    })));
+    let question = with_json_checks(state, question, String::new());
+    match state.ask(question) {
+        // Backing out of the editor keeps the result as it is.
+        Err(SchemaError::Undo { .. }) => Ok(value),
+        answer => Ok(match json_answer(answer?)? {
+            Some(edited) => secret::unredact(
+                &root_schema.definitions,
+                &root_schema.schema,
+                edited,
+                &value,
+            ),
+            None => value,
+        }),
+    }
+}
+
+pub(crate) fn get_root_title(root_schema: &RootSchema) -> Option<String> {
+    let mut title = None;
+    if let Some(metadata) = &root_schema.schema.metadata {
+        if let Some(title_ref) = &metadata.title {
+            title = Some(title_ref.clone());
+        }
+    }
+    title
+}
+
 fn update_title(mut title: Option<String>, schema: &SchemaObject) -> Option<String> {
     if let Some(metadata) = &schema.metadata {
         title = metadata.title.clone();
@@ -155,63 +321,37 @@ fn get_description(schema: &SchemaObject) -> String {
     }
 }
 
-#[allow(clippy::too_many_arguments)]
 #[allow(clippy::boxed_local)]
 fn get_single_instance(
-    definitions: &schemars::Map<String, Schema>,
-    array_info: Option<Box<ArrayValidation>>,
-    object_info: Option<Box<ObjectValidation>>,
-    subschema: Option<Box<SubschemaValidation>>,
+    state: &ParseState,
+    schema: SchemaObject,
     instance: Box<InstanceType>,
     title: Option<String>,
     name: String,
     description: String,
-    current_depth: &Cell<u16>,
 ) -> SchemaResult<Value> {
     debug!("Entered get_single_instance");
     match *instance {
-        InstanceType::String => get_string(name, description, current_depth),
-        InstanceType::Number => get_num(name, description, current_depth),
-        InstanceType::Integer => get_int(name, description, current_depth),
-        InstanceType::Boolean => get_bool(name, description, current_depth),
-        InstanceType::Array => get_array(
-            definitions,
-            array_info,
-            title,
-            name,
-            description,
-            current_depth,
-        ),
-        InstanceType::Object => get_object(
-            definitions,
-            object_info,
-            title,
-            name,
-            description,
-            current_depth,
-        ),
+        InstanceType::String => get_string(state, &schema, name, description),
+        InstanceType::Number => get_num(state, &schema, name, description),
+        InstanceType::Integer => get_int(state, &schema, name, description),
+        InstanceType::Boolean => get_bool(state, &schema, name, description),
+        InstanceType::Array => get_array(state, schema.array, title, name, description),
+        InstanceType::Object => get_object(state, schema.object, title, name, description),
         InstanceType::Null => {
             // This represents an optional enum
             // Likely the subschema will have info here.
-            get_subschema(
-                definitions,
-                title,
-                name,
-                subschema,
-                description,
-                current_depth,
-            )
+            get_subschema(state, title, name, schema.subschemas, description)
         }
     }
 }
 
 fn get_subschema(
-    definitions: &schemars::Map<String, Schema>,
+    state: &ParseState,
     title: Option<String>,
     name: String,
     subschema: Option<Box<SubschemaValidation>>,
     description: String,
-    current_depth: &Cell<u16>,
 ) -> SchemaResult<Value> {
     debug!("Entered get_subschema");
     let subschema = subschema.unwrap();
@@ -236,23 +376,19 @@ fn get_subschema(
             };
             options.push(name);
         }
-        let option = Select::new("Select one:", options.clone())
-            .with_help_message(
-                format!("{}{}{}", get_title_str(&title), name, description.as_str()).as_str(),
+        let option = state.ask(
+            Question::new(
+                QuestionKind::Select,
+                "Select one:",
+                format!("{}{}{}", get_title_str(&title), name, description.as_str()),
             )
-            .prompt_skippable()?
-            .undo(current_depth)?;
+            .with_choices(options.clone()),
+        )?;
         let position = options.iter().position(|x| x == &option).unwrap();
         let schema_object = get_schema_object(schema_vec[position].clone())?;
         if schema_object.object.is_some() {
             let title = update_title(title, &schema_object);
-            Ok(parse_schema(
-                definitions,
-                title,
-                name,
-                schema_object,
-                current_depth,
-            )?)
+            Ok(parse_schema(state, title, name, schema_object)?)
         } else if let Some(enum_values) = schema_object.enum_values {
             Ok(enum_values.first().expect("invalid schema").clone())
         } else {
@@ -265,13 +401,7 @@ fn get_subschema(
         for schema in schema_vec {
             let object = get_schema_object(schema)?;
             let title = update_title(title.clone(), &object);
-            values.push(parse_schema(
-                definitions,
-                title.clone(),
-                name.clone(),
-                object,
-                current_depth,
-            )?)
+            values.push(parse_schema(state, title.clone(), name.clone(), object)?)
         }
         match values.len() {
             1 => Ok(values.pop().unwrap()),
@@ -295,12 +425,8 @@ fn get_subschema(
         };
         let title = update_title(title, &object);
 
-        if Confirm::new("Add optional value?")
-            .with_help_message(format!("{}{}", get_title_str(&title), name).as_str())
-            .prompt_skippable()?
-            .undo(current_depth)?
-        {
-            parse_schema(definitions, title, name, object, current_depth)
+        if get_optional(state, &title, &name)? {
+            parse_schema(state, title, name, object)
         } else {
             Ok(Value::Null)
         }
@@ -309,90 +435,283 @@ fn get_subschema(
     }
 }
 
-fn get_int(name: String, description: String, current_depth: &Cell<u16>) -> SchemaResult<Value> {
+/// Whether `schema` is an object, array or enum, which can be entered as
+/// json in one go.
+fn is_compound(schema: &SchemaObject) -> bool {
+    matches!(
+        &schema.instance_type,
+        Some(SingleOrVec::Single(instance_type))
+            if matches!(**instance_type, InstanceType::Object | InstanceType::Array)
+    ) || schema
+        .subschemas
+        .as_ref()
+        .is_some_and(|subschemas| subschemas.one_of.is_some())
+}
+
+/// Asks for the value described by `schema` as json. Returns `None` if the
+/// user would rather answer its prompts.
+fn get_json(
+    state: &ParseState,
+    title: &Option<String>,
+    name: &str,
+    description: &str,
+    schema: &SchemaObject,
+) -> SchemaResult<Option<Value>> {
+    debug!("Entered get_json");
+    let mut question = Question::new(
+        QuestionKind::String,
+        "Enter as json:",
+        format!(
+            "{}{}{} (leave empty to answer prompts instead)",
+            get_title_str(title),
+            name,
+            description
+        ),
+    );
+    question.json_schema = Some(Box::new(RootSchema {
+        meta_schema: None,
+        schema: schema.clone(),
+        definitions: state.definitions.clone(),
+    }));
+    let question = with_json_checks(state, question, state.pointer());
+    json_answer(state.ask(question)?)
+}
+
+/// Adds the path and object validators for the value at `pointer`, and for
+/// every value inside it, to a question whose answer is json text. These
+/// values are never asked for one by one, so their validators run here.
+fn with_json_checks(state: &ParseState, question: Question, pointer: String) -> Question {
+    if state.options.validators.is_empty() && state.options.object_validators.is_empty() {
+        return question;
+    }
+    let validators = state.options.validators.clone();
+    let object_validators = state.options.object_validators.clone();
+    let parent = pointer
+        .rfind('/')
+        .and_then(|i| state.partial().pointer(&pointer[..i]).cloned())
+        .unwrap_or(Value::Null);
+    let restore = question.restore.clone();
+    question.with_check(move |answer| {
+        let Some(value) = answer
+            .as_str()
+            .and_then(|input| serde_json::from_str::<Value>(input).ok())
+        else {
+            return Ok(());
+        };
+        let value = match &restore {
+            Some(restore) => (restore.0)(value),
+            None => value,
+        };
+        validate::check_paths(&validators, &pointer, &value, &parent)?;
+        validate::check_objects(&object_validators, &pointer, &value)
+    })
+}
+
+/// The value in the answer to a question with [`Question::json_schema`], or
+/// `None` if the answer was left empty.
+fn json_answer(answer: Value) -> SchemaResult<Option<Value>> {
+    let input = answer.as_str().unwrap_or_default();
+    if input.trim().is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(input)
+        .map(Some)
+        .map_err(|e| SchemaError::Generic(e.to_string()))
+}
+
+fn get_optional(state: &ParseState, title: &Option<String>, name: &str) -> SchemaResult<bool> {
+    debug!("Entered get_optional");
+    Ok(state
+        .ask(Question::new(
+            QuestionKind::Confirm,
+            "Add optional value?",
+            format!("{}{}", get_title_str(title), name),
+        ))?
+        .as_bool()
+        .unwrap())
+}
+
+fn get_int(
+    state: &ParseState,
+    schema: &SchemaObject,
+    name: String,
+    description: String,
+) -> SchemaResult<Value> {
     debug!("Entered get_int");
-    Ok(json!(CustomType::<i64>::new(name.as_str())
-        .with_help_message(format!("int{description}").as_str())
-        .prompt_skippable()?
-        .undo(current_depth)?))
+    state.ask(
+        Question::new(QuestionKind::Integer, name, format!("int{description}")).with_schema(schema),
+    )
 }
 
-fn get_string(name: String, description: String, current_depth: &Cell<u16>) -> SchemaResult<Value> {
+fn get_string(
+    state: &ParseState,
+    schema: &SchemaObject,
+    name: String,
+    description: String,
+) -> SchemaResult<Value> {
     debug!("Entered get_string");
-    Ok(Value::String(
-        Text::new(name.as_str())
-            .with_help_message(format!("string{description}").as_str())
-            .prompt_skippable()?
-            .undo(current_depth)?,
+    if let Some(provider) = options::at_path(&state.options.path_choices, &state.pointer()) {
+        if let Some(value) = get_choice(state, schema, name.clone(), &description, provider)? {
+            return Ok(value);
+        }
+    }
+    if schema.extensions.get("contentEncoding") == Some(&json!("base64")) {
+        let help = format!("{name}{description}");
+        if let Some(bytes) = get_bytes(state, &help, Constraints::default(), false)? {
+            let bytes = bytes
+                .iter()
+                .filter_map(|byte| byte.as_u64().map(|byte| byte as u8))
+                .collect::<Vec<_>>();
+            return Ok(Value::String(BASE64_STANDARD.encode(bytes)));
+        }
+    }
+    state.ask(
+        Question::new(QuestionKind::String, name, format!("string{description}"))
+            .with_schema(schema),
+    )
+}
+
+/// Asks for a value with a prompt registered in the options.
+fn get_custom(
+    state: &ParseState,
+    prompt: Arc<dyn InteractivePrompt>,
+    name: &str,
+    schema: &SchemaObject,
+) -> SchemaResult<Value> {
+    let check = prompt.clone();
+    let question = prompt
+        .question(name, schema)
+        .with_check(move |answer| check.validate(answer));
+    Ok(prompt.value(state.ask(question)?))
+}
+
+/// Asks for one of the values `provider` returns. `None` if it returns none,
+/// in which case the value is asked for as usual.
+fn get_choice(
+    state: &ParseState,
+    schema: &SchemaObject,
+    name: String,
+    description: &str,
+    provider: &ChoiceProvider,
+) -> SchemaResult<Option<Value>> {
+    let choices = provider();
+    if choices.is_empty() {
+        return Ok(None);
+    }
+    let question = Question::new(QuestionKind::Select, name, format!("string{description}"))
+        .with_schema(schema)
+        .with_choices(choices);
+    state.ask(question).map(Some)
+}
+
+/// Asks how some bytes will be written and then for the bytes, which are
+/// returned as an array of byte values. `None` means the user would rather
+/// enter them one element at a time, which is only offered if `elements`.
+pub(crate) fn get_bytes(
+    state: &ParseState,
+    help: &str,
+    constraints: Constraints,
+    elements: bool,
+) -> SchemaResult<Option<Vec<Value>>> {
+    const ELEMENTS: &str = "One element at a time";
+    let mut choices = ByteInput::ALL
+        .iter()
+        .map(|input| input.label().to_string())
+        .collect::<Vec<_>>();
+    if elements {
+        choices.push(ELEMENTS.to_string());
+    }
+    let answer = state.ask(
+        Question::new(QuestionKind::Select, "Enter the bytes as:", help).with_choices(choices),
+    )?;
+    let Some(input) = ByteInput::ALL
+        .into_iter()
+        .find(|input| answer.as_str() == Some(input.label()))
+    else {
+        return Ok(None);
+    };
+    let message = match input {
+        ByteInput::File => "File:",
+        _ => "Bytes:",
+    };
+    let mut question = Question::new(QuestionKind::String, message, help);
+    question.constraints = constraints;
+    question.bytes = Some(input);
+    Ok(Some(
+        state.ask(question)?.as_array().cloned().unwrap_or_default(),
     ))
 }
 
-fn get_num(name: String, description: String, current_depth: &Cell<u16>) -> SchemaResult<Value> {
+fn get_num(
+    state: &ParseState,
+    schema: &SchemaObject,
+    name: String,
+    description: String,
+) -> SchemaResult<Value> {
     debug!("Entered get_num");
-    Ok(json!(CustomType::<f64>::new(name.as_str())
-        .with_help_message(format!("num{description}").as_str())
-        .prompt_skippable()?
-        .undo(current_depth)?))
+    state.ask(
+        Question::new(QuestionKind::Number, name, format!("num{description}")).with_schema(schema),
+    )
 }
 
-fn get_bool(name: String, description: String, current_depth: &Cell<u16>) -> SchemaResult<Value> {
+fn get_bool(
+    state: &ParseState,
+    schema: &SchemaObject,
+    name: String,
+    description: String,
+) -> SchemaResult<Value> {
     debug!("Entered get_bool");
-    Ok(json!(CustomType::<bool>::new(name.as_str())
-        .with_help_message(format!("bool{description}").as_str())
-        .prompt_skippable()?
-        .undo(current_depth)?))
+    state.ask(
+        Question::new(QuestionKind::Boolean, name, format!("bool{description}"))
+            .with_schema(schema),
+    )
+}
+
+fn add_element(
+    state: &ParseState,
+    title: &Option<String>,
+    name: &str,
+    description: &str,
+) -> SchemaResult<bool> {
+    Ok(state
+        .ask(Question::new(
+            QuestionKind::Confirm,
+            "Add element?",
+            format!("{}{}{}", get_title_str(title), name, description),
+        ))?
+        .as_bool()
+        .unwrap())
 }
 
 fn get_array(
-    definitions: &schemars::Map<String, Schema>,
+    state: &ParseState,
     array_info: Option<Box<ArrayValidation>>,
     title: Option<String>,
     name: String,
     description: String,
-    current_depth: &Cell<u16>,
 ) -> SchemaResult<Value> {
     debug!("Entered get_array");
     let array_info = array_info.unwrap();
     let range = array_info.min_items..array_info.max_items;
     debug!("array range: {range:?}");
 
-    let mut array = Vec::new();
-    match array_info.items.unwrap() {
+    let array = match array_info.items.unwrap() {
         SingleOrVec::Single(schema) => {
             debug!("Single type array");
-            array = (0..).recurse_iter(current_depth, |i| {
-                if let Some(end) = range.end {
-                    if array.len() == end as usize {
-                        return Ok(RecurseLoop::Return(None));
-                    }
-                }
-
-                let start = range.start.unwrap_or_default();
-                if i >= start as usize
-                    && !Confirm::new("Add element?")
-                        .with_help_message(
-                            format!("{}{}{}", get_title_str(&title), name, description).as_str(),
-                        )
-                        .prompt_skippable()?
-                        .undo(current_depth)?
-                {
-                    return Ok(RecurseLoop::Return(None));
-                }
-
-                let object = get_schema_object(*schema.clone())?;
-                let value = parse_schema(
-                    definitions,
-                    title.clone(),
-                    format!("{}[{}]", name.clone(), i),
-                    object,
-                    current_depth,
-                )?;
-                Ok(RecurseLoop::Continue(value))
-            })?;
+            ArrayMenu {
+                title: &title,
+                name: &name,
+                description: &description,
+                item: get_schema_object(*schema)?,
+                min_items: range.start.unwrap_or_default() as usize,
+                max_items: range.end.map(|end| end as usize),
+                unique_items: array_info.unique_items == Some(true),
+            }
+            .parse(state)?
         }
         SingleOrVec::Vec(schemas) => {
             debug!("Vec type array");
-            array = (0..).recurse_iter(current_depth, |i| {
+            (0..).recurse_iter(&state.current_depth, |i| {
                 if let Some(end) = range.end {
                     if i == end as usize {
                         return Ok(RecurseLoop::Return(None));
@@ -402,60 +721,95 @@ fn get_array(
                 let schema = schemas[i].clone();
 
                 let start = range.start.unwrap_or_default();
-                if i >= start as usize
-                    && !Confirm::new("Add element?")
-                        .with_help_message(
-                            format!("{}{}{}", get_title_str(&title), name, description).as_str(),
-                        )
-                        .prompt_skippable()?
-                        .undo(current_depth)?
-                {
+                if i >= start as usize && !add_element(state, &title, &name, &description)? {
                     return Ok(RecurseLoop::Return(None));
                 }
                 let object = get_schema_object(schema)?;
-                let value = parse_schema(
-                    definitions,
-                    title.clone(),
-                    format!("{}.{}", name.clone(), i),
-                    object,
-                    current_depth,
-                )?;
+                let value = state.with_segment(i, || {
+                    parse_schema(
+                        state,
+                        title.clone(),
+                        format!("{}.{}", name.clone(), i),
+                        object,
+                    )
+                })?;
 
                 Ok(RecurseLoop::Continue(value))
-            })?;
+            })?
         }
     };
     Ok(Value::Array(array))
 }
 
 fn get_object(
-    definitions: &schemars::Map<String, Schema>,
+    state: &ParseState,
     object_info: Option<Box<ObjectValidation>>,
     title: Option<String>,
     _name: String,
     _description: String,
-    current_depth: &Cell<u16>,
 ) -> SchemaResult<Value> {
     debug!("Entered get_object");
-    let map = object_info
-        .unwrap()
-        .properties
+    let properties = object_info.unwrap().properties;
+    let map = properties
         .iter()
-        .recurse_iter(current_depth, |(name, schema)| {
+        .recurse_iter(&state.current_depth, |(name, schema)| {
             let schema_object = get_schema_object(schema.clone())?;
-            let object = parse_schema(
-                definitions,
-                title.clone(),
-                name.to_string(),
-                schema_object,
-                current_depth,
-            )?;
+            let object = state.with_segment(name, || {
+                parse_schema(state, title.clone(), name.to_string(), schema_object)
+            })?;
             Ok(RecurseLoop::Continue((name, object)))
         })?
         .into_iter()
         .map(|(name, object)| (name.clone(), object))
         .collect::<Map<String, Value>>();
-    Ok(Value::Object(map))
+    let mut value = Value::Object(map);
+    let Some(validator) = options::at_path(&state.options.object_validators, &state.pointer())
+    else {
+        return Ok(value);
+    };
+    while let Err(message) = (validator.check)(&value) {
+        let field = match validator.fields.as_slice() {
+            [field] => {
+                state.notify(format!("{message}, please enter {field} again"));
+                field.clone()
+            }
+            fields => {
+                let question = Question::new(
+                    QuestionKind::Select,
+                    format!("{message}. Which field do you want to enter again?"),
+                    "",
+                )
+                .with_choices(fields.to_vec());
+                let answer = state.ask(question)?;
+                answer.as_str().unwrap_or_default().to_string()
+            }
+        };
+        let Some(schema) = properties.get(&field) else {
+            return Err(SchemaError::Generic(format!(
+                "{field} is not a field of {}",
+                state.pointer()
+            )));
+        };
+        let schema_object = get_schema_object(schema.clone())?;
+        value[&field] = state.with_segment(&field, || {
+            parse_schema(state, title.clone(), field.clone(), schema_object)
+        })?;
+    }
+    Ok(value)
+}
+
+/// `schema` with `value` as its default, and with the fields of `value` as
+/// the defaults of its properties, so that asking for it starts from `value`.
+pub(crate) fn with_default(mut schema: SchemaObject, value: &Value) -> SchemaObject {
+    if let (Some(object_info), Value::Object(map)) = (&mut schema.object, value) {
+        for (name, property) in object_info.properties.iter_mut() {
+            if let (Schema::Object(property_object), Some(value)) = (property, map.get(name)) {
+                *property_object = with_default(property_object.clone(), value);
+            }
+        }
+    }
+    schema.metadata().default = Some(value.clone());
+    schema
 }
 
 fn get_schema_object(schema: Schema) -> SchemaResult<SchemaObject> {
@@ -466,7 +820,7 @@ fn get_schema_object(schema: Schema) -> SchemaResult<SchemaObject> {
     }
 }
 
-fn get_schema_object_ref(schema: &Schema) -> SchemaResult<&SchemaObject> {
+pub(crate) fn get_schema_object_ref(schema: &Schema) -> SchemaResult<&SchemaObject> {
     debug!("Entered get_schema_object_ref");
     match schema {
         Schema::Bool(_) => Err(SchemaError::SchemaIsBool),
@@ -510,6 +864,7 @@ mod tests {
     }
 
     /// Doc comment on struct
+    #[allow(dead_code)]
     #[derive(JsonSchema, Serialize, Deserialize, Debug)]
     pub struct MyStruct3 {
         /// Doc comment on field
@@ -0,0 +1,336 @@
+#[cfg(feature = "web")]
+use std::net::SocketAddr;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Debug, Formatter},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use schemars::{
+    schema::{InstanceType, RootSchema, Schema, SingleOrVec},
+    JsonSchema,
+};
+use serde_json::Value;
+
+use crate::{
+    error::{SchemaError, SchemaResult},
+    traits::InteractivePrompt,
+};
+
+/// Returns the values a string may take, for choices that are only known at
+/// runtime.
+pub type ChoiceProvider = dyn Fn() -> Vec<String> + Send + Sync;
+
+/// Checks a candidate value against the partially built object that holds
+/// it, explaining what is wrong with it if anything.
+pub type Validator = dyn Fn(&Value, &Value) -> Result<(), String> + Send + Sync;
+
+/// Checks a value, explaining what is wrong with it if anything.
+pub(crate) type Check = dyn Fn(&Value) -> Result<(), String> + Send + Sync;
+
+/// Puts back the secrets that were masked in a value shown to the user.
+pub(crate) type Restore = dyn Fn(Value) -> Value + Send + Sync;
+
+/// A check of a whole object, along with the fields it involves.
+pub(crate) struct ObjectValidator {
+    pub(crate) fields: Vec<String>,
+    pub(crate) check: Box<Check>,
+}
+
+/// A closure registered with [`ParseOptions`], shared between its clones.
+pub(crate) struct Callback<F: ?Sized>(pub(crate) Arc<F>);
+
+impl<F: ?Sized> Clone for Callback<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> Debug for Callback<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("<closure>")
+    }
+}
+
+/// Options controlling how a type is parsed interactively.
+///
+/// ```no_run
+/// use interactive_parse::{InteractiveParseObj, ParseOptions};
+///
+/// let options = ParseOptions::new().with_session_file(".my-wizard-session.json");
+/// let value = Vec::<u32>::parse_to_obj_with(&options).unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    pub(crate) session_file: Option<PathBuf>,
+    pub(crate) json_rpc: bool,
+    pub(crate) json_entry: bool,
+    pub(crate) final_edit: bool,
+    pub(crate) path_choices: BTreeMap<String, Callback<ChoiceProvider>>,
+    pub(crate) definition_choices: BTreeMap<String, Callback<ChoiceProvider>>,
+    pub(crate) prompts: BTreeMap<String, Callback<dyn InteractivePrompt>>,
+    pub(crate) validators: BTreeMap<String, Callback<Validator>>,
+    pub(crate) object_validators: BTreeMap<String, Callback<ObjectValidator>>,
+    #[cfg(feature = "form")]
+    pub(crate) form: bool,
+    #[cfg(feature = "web")]
+    pub(crate) web_form: Option<SocketAddr>,
+}
+
+impl ParseOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Saves every answer to `path` as the user goes. If the parse is
+    /// interrupted, the next parse with the same file offers to resume where
+    /// the user left off. The file is removed once the parse completes, and
+    /// ignored if the schema has changed since it was written.
+    pub fn with_session_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.session_file = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Before prompting for an object, array or enum, offers to enter the
+    /// whole value as json instead. The json is checked against the schema,
+    /// and leaving it empty falls back to the usual prompts.
+    pub fn with_json_entry(mut self) -> Self {
+        self.json_entry = true;
+        self
+    }
+
+    /// Once every question has been answered, offers the result as json in
+    /// `$EDITOR` for any last changes. The edited json is checked against the
+    /// schema when the editor is closed.
+    pub fn with_final_edit(mut self) -> Self {
+        self.final_edit = true;
+        self
+    }
+
+    /// Asks for the string at the json pointer `path` by picking one of the
+    /// values `provider` returns, rather than typing it. A `*` segment in
+    /// `path` matches any field or index, as in `/servers/*/region`.
+    pub fn with_path_choices(
+        mut self,
+        path: impl Into<String>,
+        provider: impl Fn() -> Vec<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.path_choices
+            .insert(path.into(), Callback(Arc::new(provider)));
+        self
+    }
+
+    /// Like [`ParseOptions::with_path_choices`], for every value of the
+    /// `$ref` definition `name`, e.g. `Branch` for a `struct Branch(String)`.
+    /// A parse fails with [`SchemaError::ChoicesNotString`] if the
+    /// definition is not a string.
+    pub fn with_definition_choices(
+        mut self,
+        name: impl Into<String>,
+        provider: impl Fn() -> Vec<String> + Send + Sync + 'static,
+    ) -> Self {
+        self.definition_choices
+            .insert(name.into(), Callback(Arc::new(provider)));
+        self
+    }
+
+    /// Checks the value at the json pointer `path` with `validator` once it
+    /// has been entered, asking for it again if it is rejected. The validator
+    /// is given the value and its parent as entered so far, which holds the
+    /// fields that come before it. A `*` segment in `path` matches any field
+    /// or index.
+    pub fn with_validator(
+        mut self,
+        path: impl Into<String>,
+        validator: impl Fn(&Value, &Value) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.validators
+            .insert(path.into(), Callback(Arc::new(validator)));
+        self
+    }
+
+    /// Checks the object at the json pointer `path` with `validator` once all
+    /// of its fields have been entered, for rules that span several of them
+    /// such as `min <= max`. If it is rejected the user picks which of
+    /// `fields` to enter again, until the object passes. A `*` segment in
+    /// `path` matches any field or index.
+    ///
+    /// # Panics
+    ///
+    /// If `fields` is empty, since there would be nothing to enter again.
+    pub fn with_object_validator(
+        mut self,
+        path: impl Into<String>,
+        fields: impl IntoIterator<Item = impl Into<String>>,
+        validator: impl Fn(&Value) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        let validator = ObjectValidator {
+            fields: fields.into_iter().map(Into::into).collect(),
+            check: Box::new(validator),
+        };
+        assert!(
+            !validator.fields.is_empty(),
+            "an object validator needs at least one field to enter again"
+        );
+        self.object_validators
+            .insert(path.into(), Callback(Arc::new(validator)));
+        self
+    }
+
+    /// Asks for every value of the `$ref` definition `name` with `prompt`,
+    /// instead of walking the definition's schema. Values whose schema is
+    /// inlined rather than a definition, such as the root type, use the
+    /// prompt if their schema's title is `name`. A parse fails with
+    /// [`SchemaError::UnusedPrompt`] if neither is found in its schema, as is
+    /// the case for types like `url::Url` that have neither a definition nor
+    /// a title.
+    pub fn with_prompt(
+        mut self,
+        name: impl Into<String>,
+        prompt: impl InteractivePrompt + 'static,
+    ) -> Self {
+        self.prompts.insert(name.into(), Callback(Arc::new(prompt)));
+        self
+    }
+
+    /// Like [`ParseOptions::with_prompt`], for the definition or root schema
+    /// of `T`.
+    pub fn with_prompt_for<T: JsonSchema>(self, prompt: impl InteractivePrompt + 'static) -> Self {
+        self.with_prompt(T::schema_name(), prompt)
+    }
+
+    /// Hands the questions to another process over JSON-RPC on stdin and
+    /// stdout instead of prompting on the terminal. See [`crate::rpc`] for
+    /// the protocol. Every other option applies as on the terminal, except
+    /// for session files, which make the parse fail with
+    /// [`SchemaError::UnsupportedOption`].
+    pub fn with_json_rpc(mut self) -> Self {
+        self.json_rpc = true;
+        self
+    }
+
+    /// Shows every field at once in a full-screen form instead of asking one
+    /// question at a time.
+    ///
+    /// The form can't be combined with session files, json entry, the final
+    /// edit, choice providers, custom prompts or validators. The parse fails
+    /// with [`SchemaError::UnsupportedOption`] if any of them is set.
+    #[cfg(feature = "form")]
+    pub fn with_form(mut self) -> Self {
+        self.form = true;
+        self
+    }
+
+    /// Serves an html form on `addr` instead of prompting on the terminal,
+    /// and returns once a valid form has been submitted. The address is
+    /// logged at the info level, and bind a [`WebForm`](crate::web::WebForm)
+    /// directly to show it some other way.
+    ///
+    /// `addr` has to be a loopback address such as `127.0.0.1`, otherwise
+    /// the parse fails with [`SchemaError::NotLoopback`]. Like the
+    /// [full-screen form](Self::with_form), the web form can't be combined
+    /// with options that work question by question.
+    #[cfg(feature = "web")]
+    pub fn with_web_form(mut self, addr: SocketAddr) -> Self {
+        self.web_form = Some(addr);
+        self
+    }
+}
+
+impl ParseOptions {
+    /// Fails if an option is set that `frontend`, which shows every field at
+    /// once instead of asking for them one by one, can't apply.
+    #[cfg(any(feature = "form", feature = "web"))]
+    pub(crate) fn check_form_options(&self, frontend: &'static str) -> SchemaResult<()> {
+        let unsupported = [
+            (self.session_file.is_some(), "session files"),
+            (self.json_entry, "json entry"),
+            (self.final_edit, "the final edit"),
+            (
+                !self.path_choices.is_empty() || !self.definition_choices.is_empty(),
+                "choice providers",
+            ),
+            (!self.prompts.is_empty(), "custom prompts"),
+            (
+                !self.validators.is_empty() || !self.object_validators.is_empty(),
+                "validators",
+            ),
+        ];
+        match unsupported.into_iter().find(|(set, _)| *set) {
+            Some((_, option)) => Err(SchemaError::UnsupportedOption { frontend, option }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that every definition with registered choices is a string.
+    pub(crate) fn check_choices(&self, root_schema: &RootSchema) -> SchemaResult<()> {
+        let string = Some(SingleOrVec::Single(Box::new(InstanceType::String)));
+        let not_string =
+            self.definition_choices
+                .keys()
+                .find(|name| match root_schema.definitions.get(*name) {
+                    Some(Schema::Object(schema)) => schema.instance_type != string,
+                    Some(Schema::Bool(_)) => true,
+                    None => false,
+                });
+        match not_string {
+            Some(name) => Err(SchemaError::ChoicesNotString { name: name.clone() }),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that every registered prompt names a definition or a title in
+    /// `root_schema`.
+    pub(crate) fn check_prompts(&self, root_schema: &RootSchema) -> SchemaResult<()> {
+        if self.prompts.is_empty() {
+            return Ok(());
+        }
+        let mut names = root_schema
+            .definitions
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        titles(
+            &serde_json::to_value(root_schema).unwrap_or_default(),
+            &mut names,
+        );
+        match self.prompts.keys().find(|name| !names.contains(*name)) {
+            Some(name) => Err(SchemaError::UnusedPrompt { name: name.clone() }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Every title in the json form of a schema.
+fn titles(value: &Value, names: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(title)) = map.get("title") {
+                names.insert(title.clone());
+            }
+            map.values().for_each(|value| titles(value, names));
+        }
+        Value::Array(values) => values.iter().for_each(|value| titles(value, names)),
+        _ => {}
+    }
+}
+
+/// Finds the callback registered for the json pointer `pointer`, where `*`
+/// segments of a registered path match any segment.
+pub(crate) fn at_path<'a, F: ?Sized>(
+    callbacks: &'a BTreeMap<String, Callback<F>>,
+    pointer: &str,
+) -> Option<&'a F> {
+    callbacks
+        .iter()
+        .find(|(path, _)| {
+            let mut segments = pointer.split('/');
+            path.split('/').all(|pattern| {
+                segments
+                    .next()
+                    .is_some_and(|x| pattern == "*" || pattern == x)
+            }) && segments.next().is_none()
+        })
+        .map(|(_, callback)| callback.0.as_ref())
+}
@@ -0,0 +1,928 @@
+use std::sync::Arc;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::NaiveDate;
+use inquire::{
+    list_option::ListOption,
+    validator::{ErrorMessage, Validation},
+    Confirm, CustomType, CustomUserError, DateSelect, Editor, MultiSelect, Password,
+    PasswordDisplayMode, Select, Text,
+};
+use schemars::schema::{RootSchema, SchemaObject};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    complete::{ListCompleter, PathCompleter},
+    datetime,
+    error::SchemaResult,
+    options::{Callback, Check, Restore},
+    secret,
+    validate::validate_value,
+};
+
+/// The kind of answer a [`Question`] expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuestionKind {
+    /// A yes/no question about the shape of the value, answered with a bool.
+    Confirm,
+    /// One of `choices`, answered with the chosen string.
+    Select,
+    /// Any number of `choices`, answered with an array of the chosen strings
+    /// in the order of `choices`.
+    MultiSelect,
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+/// Limits on the answer to a [`Question`], taken from the schema.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Constraints {
+    pub minimum: Option<f64>,
+    pub maximum: Option<f64>,
+    pub exclusive_minimum: Option<f64>,
+    pub exclusive_maximum: Option<f64>,
+    pub multiple_of: Option<f64>,
+    pub min_length: Option<u32>,
+    pub max_length: Option<u32>,
+    /// Not enforced, but useful to show to the user.
+    pub pattern: Option<String>,
+    /// The schema `format`, e.g. `uint8` or `date-time`.
+    pub format: Option<String>,
+    pub min_items: Option<u32>,
+    pub max_items: Option<u32>,
+    /// Whether elements must differ from each other, as in a set.
+    pub unique_items: bool,
+}
+
+impl Constraints {
+    pub(crate) fn from_schema(schema: &SchemaObject) -> Self {
+        let mut constraints = Self {
+            format: schema.format.clone(),
+            ..Default::default()
+        };
+        if let Some(number) = &schema.number {
+            constraints.minimum = number.minimum;
+            constraints.maximum = number.maximum;
+            constraints.exclusive_minimum = number.exclusive_minimum;
+            constraints.exclusive_maximum = number.exclusive_maximum;
+            constraints.multiple_of = number.multiple_of;
+        }
+        if let Some(string) = &schema.string {
+            constraints.min_length = string.min_length;
+            constraints.max_length = string.max_length;
+            constraints.pattern = string.pattern.clone();
+        }
+        if let Some(array) = &schema.array {
+            constraints.min_items = array.min_items;
+            constraints.max_items = array.max_items;
+            constraints.unique_items = array.unique_items == Some(true);
+        }
+        // schemars only emits a minimum for unsigned integers, so the rest of
+        // the range comes from the format.
+        let range = match schema.format.as_deref() {
+            Some("int8") => Some((i8::MIN as f64, i8::MAX as f64)),
+            Some("int16") => Some((i16::MIN as f64, i16::MAX as f64)),
+            Some("int32") => Some((i32::MIN as f64, i32::MAX as f64)),
+            Some("uint8") => Some((0.0, u8::MAX as f64)),
+            Some("uint16") => Some((0.0, u16::MAX as f64)),
+            Some("uint32") => Some((0.0, u32::MAX as f64)),
+            Some("uint64" | "uint") => Some((0.0, u64::MAX as f64)),
+            _ => None,
+        };
+        if let Some((min, max)) = range {
+            constraints.minimum = Some(constraints.minimum.map_or(min, |x| x.max(min)));
+            constraints.maximum = Some(constraints.maximum.map_or(max, |x| x.min(max)));
+        }
+        constraints
+    }
+
+    /// Checks `answer` against these constraints.
+    pub fn check(&self, answer: &Value) -> Result<(), String> {
+        if let Some(number) = answer.as_f64() {
+            if let Some(minimum) = self.minimum {
+                if number < minimum {
+                    return Err(format!("must be at least {minimum}"));
+                }
+            }
+            if let Some(maximum) = self.maximum {
+                if number > maximum {
+                    return Err(format!("must be at most {maximum}"));
+                }
+            }
+            if let Some(minimum) = self.exclusive_minimum {
+                if number <= minimum {
+                    return Err(format!("must be greater than {minimum}"));
+                }
+            }
+            if let Some(maximum) = self.exclusive_maximum {
+                if number >= maximum {
+                    return Err(format!("must be less than {maximum}"));
+                }
+            }
+            if let Some(multiple_of) = self.multiple_of {
+                if (number / multiple_of).fract() != 0.0 {
+                    return Err(format!("must be a multiple of {multiple_of}"));
+                }
+            }
+        }
+        if let Some(string) = answer.as_str() {
+            let len = string.chars().count();
+            if let Some(min_length) = self.min_length {
+                if len < min_length as usize {
+                    return Err(format!("must be at least {min_length} characters"));
+                }
+            }
+            if let Some(max_length) = self.max_length {
+                if len > max_length as usize {
+                    return Err(format!("must be at most {max_length} characters"));
+                }
+            }
+            if let Some(format) = &self.format {
+                datetime::check(format, string)?;
+            }
+        }
+        if let Some(array) = answer.as_array() {
+            if let Some(min_items) = self.min_items {
+                if array.len() < min_items as usize {
+                    return Err(format!("must have at least {min_items} elements"));
+                }
+            }
+            if let Some(max_items) = self.max_items {
+                if array.len() > max_items as usize {
+                    return Err(format!("must have at most {max_items} elements"));
+                }
+            }
+            if self.unique_items {
+                for (i, element) in array.iter().enumerate() {
+                    if array[..i].contains(element) {
+                        return Err(format!("{element} is already in the list"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How the answer to a question with [`Question::bytes`] is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteInput {
+    /// Pairs of hex digits, optionally separated by spaces or colons.
+    Hex,
+    Base64,
+    /// Text whose UTF-8 encoding is the bytes.
+    Text,
+    /// The path of a file to read the bytes from.
+    File,
+}
+
+impl ByteInput {
+    pub const ALL: [ByteInput; 4] = [
+        ByteInput::Hex,
+        ByteInput::Base64,
+        ByteInput::Text,
+        ByteInput::File,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ByteInput::Hex => "Hex",
+            ByteInput::Base64 => "Base64",
+            ByteInput::Text => "UTF-8 text",
+            ByteInput::File => "Read from a file",
+        }
+    }
+
+    /// Reads the bytes written in `input`.
+    pub fn decode(self, input: &str) -> Result<Vec<u8>, String> {
+        match self {
+            ByteInput::Hex => {
+                let digits = input
+                    .trim()
+                    .trim_start_matches("0x")
+                    .chars()
+                    .filter(|c| !c.is_whitespace() && *c != ':')
+                    .collect::<Vec<_>>();
+                if digits.len() % 2 != 0 {
+                    return Err("hex must have an even number of digits".to_string());
+                }
+                digits
+                    .chunks(2)
+                    .map(|pair| {
+                        let pair = pair.iter().collect::<String>();
+                        u8::from_str_radix(&pair, 16).map_err(|_| format!("{pair:?} is not hex"))
+                    })
+                    .collect()
+            }
+            ByteInput::Base64 => BASE64_STANDARD
+                .decode(input.trim())
+                .map_err(|e| format!("invalid base64: {e}")),
+            ByteInput::Text => Ok(input.as_bytes().to_vec()),
+            ByteInput::File => {
+                std::fs::read(input.trim()).map_err(|e| format!("could not read {input}: {e}"))
+            }
+        }
+    }
+}
+
+/// Rules for the answer to a question with [`Question::file_path`], taken
+/// from the `x-path` schema extension, e.g.
+/// `{"mustExist": true, "directory": false, "extensions": ["toml"]}`. Fields
+/// with `"format": "path"` or `"x-path": true` take any path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilePath {
+    pub must_exist: bool,
+    /// Whether the path must be a directory rather than a file, if it exists.
+    pub directory: bool,
+    /// The extensions, without a dot, that a file may have. Any extension is
+    /// fine if this is empty.
+    pub extensions: Vec<String>,
+}
+
+impl FilePath {
+    fn from_schema(schema: &SchemaObject) -> Option<Self> {
+        match schema.extensions.get("x-path") {
+            Some(Value::Object(rules)) => Some(Self {
+                must_exist: rules.get("mustExist") == Some(&Value::Bool(true)),
+                directory: rules.get("directory") == Some(&Value::Bool(true)),
+                extensions: rules
+                    .get("extensions")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                    .map(|extension| extension.trim_start_matches('.').to_string())
+                    .collect(),
+            }),
+            Some(Value::Bool(true)) => Some(Self::default()),
+            _ if schema.format.as_deref() == Some("path") => Some(Self::default()),
+            _ => None,
+        }
+    }
+
+    /// Whether a file with this name is allowed by `extensions`.
+    pub(crate) fn allows_extension(&self, path: &std::path::Path) -> bool {
+        self.extensions.is_empty()
+            || path.extension().is_some_and(|extension| {
+                self.extensions
+                    .iter()
+                    .any(|x| x.eq_ignore_ascii_case(&extension.to_string_lossy()))
+            })
+    }
+
+    /// Checks that `input` names a path that follows these rules.
+    pub fn check(&self, input: &str) -> Result<(), String> {
+        let path = std::path::Path::new(input);
+        if self.must_exist && !path.exists() {
+            return Err(format!("{input} does not exist"));
+        }
+        if self.directory {
+            if path.exists() && !path.is_dir() {
+                return Err(format!("{input} is not a directory"));
+            }
+        } else if !self.allows_extension(path) {
+            let extensions = self
+                .extensions
+                .iter()
+                .map(|x| format!(".{x}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(format!("must end in {extensions}"));
+        }
+        Ok(())
+    }
+}
+
+fn byte_values(bytes: Vec<u8>) -> Vec<Value> {
+    bytes.into_iter().map(Value::from).collect()
+}
+
+/// A property of the objects imported by a question with
+/// [`Question::columns`].
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    /// The question each cell of the column must answer.
+    pub question: Question,
+    /// Whether an empty or missing cell means `null`.
+    pub optional: bool,
+}
+
+impl Column {
+    /// The value of a cell, or of a column missing from the file when `cell`
+    /// is `None`.
+    fn parse(&self, cell: Option<&str>) -> Result<Value, String> {
+        match cell {
+            // An empty cell is only an empty string if the column needs one.
+            Some(cell)
+                if !cell.is_empty()
+                    || (self.question.kind == QuestionKind::String && !self.optional) =>
+            {
+                self.question.parse(cell)
+            }
+            _ if self.optional => Ok(Value::Null),
+            _ => self
+                .question
+                .default
+                .clone()
+                .ok_or_else(|| "missing value".to_string()),
+        }
+    }
+
+    fn validate(&self, value: &Value) -> Result<(), String> {
+        match value {
+            Value::Null if self.optional => Ok(()),
+            value => self.question.validate(value),
+        }
+    }
+}
+
+/// A single prompt produced while walking a schema.
+///
+/// Answers are json values: a bool for [`QuestionKind::Confirm`] and
+/// [`QuestionKind::Boolean`], one of `choices` for [`QuestionKind::Select`],
+/// an array of them for [`QuestionKind::MultiSelect`], and otherwise a value of the matching json type.
+#[derive(Debug, Clone)]
+pub struct Question {
+    /// The json pointer of the value being asked about.
+    pub path: String,
+    pub kind: QuestionKind,
+    pub message: String,
+    pub help: String,
+    /// The full doc comment of the value being asked about.
+    pub description: Option<String>,
+    pub choices: Vec<String>,
+    pub default: Option<Value>,
+    /// Realistic answers taken from the schema's `examples`, which are
+    /// suggested while typing or offered in a list.
+    pub examples: Vec<Value>,
+    pub constraints: Constraints,
+    /// How the answer is best entered, taken from the `x-widget` schema
+    /// extension or else the `format`. `textarea` answers are written in
+    /// `$EDITOR` rather than on a single line.
+    pub widget: Option<String>,
+    /// Whether the answer is a secret, such as a password, that should be
+    /// masked while typed and never shown.
+    pub secret: bool,
+    /// Set on [`QuestionKind::String`] questions that are answered with
+    /// several elements at once: an array of them, or text separating them
+    /// with commas or new lines, or `@path` to read them from a file. Each
+    /// element must answer this question.
+    pub elements: Option<Box<Question>>,
+    /// Set on [`QuestionKind::String`] questions that are answered with the
+    /// path of a CSV file, whose header names these columns and whose rows
+    /// each become an object. An array of such objects is also accepted.
+    pub columns: Option<Vec<Column>>,
+    /// Set on [`QuestionKind::String`] questions that are answered with
+    /// bytes written this way. An array of byte values is also accepted.
+    pub bytes: Option<ByteInput>,
+    /// Set on [`QuestionKind::String`] questions that are answered with a
+    /// filesystem path, which is completed from the local filesystem.
+    pub file_path: Option<FilePath>,
+    /// Set on [`QuestionKind::String`] questions that are answered with the
+    /// json of a whole value, which must match this schema. An empty answer
+    /// means the value will be prompted for instead.
+    pub json_schema: Option<Box<RootSchema>>,
+    /// Puts back the secrets masked in the default of a question with
+    /// [`Question::json_schema`] before the answer is checked.
+    pub(crate) restore: Option<Callback<Restore>>,
+    /// Further checks on the answer, such as those of a custom prompt, that
+    /// run once it has the right shape.
+    pub(crate) checks: Vec<Callback<Check>>,
+}
+
+impl Question {
+    pub fn new(kind: QuestionKind, message: impl Into<String>, help: impl Into<String>) -> Self {
+        Self {
+            path: String::new(),
+            kind,
+            message: message.into(),
+            help: help.into(),
+            description: None,
+            choices: Vec::new(),
+            default: None,
+            examples: Vec::new(),
+            constraints: Constraints::default(),
+            widget: None,
+            secret: false,
+            elements: None,
+            columns: None,
+            bytes: None,
+            file_path: None,
+            json_schema: None,
+            restore: None,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Puts `notice`, such as why the last answer was rejected, at the start
+    /// of the help.
+    pub(crate) fn with_notice(mut self, notice: String) -> Self {
+        self.help = match self.help.is_empty() {
+            true => notice,
+            false => format!("{notice} ({})", self.help),
+        };
+        self
+    }
+
+    pub fn with_choices(mut self, choices: Vec<String>) -> Self {
+        self.choices = choices;
+        self
+    }
+
+    /// Fills in the description, default and constraints of a question
+    /// about the value described by `schema`.
+    pub fn with_schema(mut self, schema: &SchemaObject) -> Self {
+        if let Some(metadata) = &schema.metadata {
+            self.description = metadata.description.clone();
+            self.default = metadata.default.clone();
+            self.examples = metadata.examples.clone();
+        }
+        self.constraints = Constraints::from_schema(schema);
+        self.widget = schema
+            .extensions
+            .get("x-widget")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .or_else(|| schema.format.clone());
+        self.secret = secret::is_secret(schema);
+        self.file_path = FilePath::from_schema(schema);
+        self
+    }
+
+    /// Adds a check that answers must pass on top of fitting the question.
+    /// It is given the answer as it will be returned, e.g. a date rather than
+    /// `today`.
+    pub(crate) fn with_check(
+        mut self,
+        check: impl Fn(&Value) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.checks.push(Callback(Arc::new(check)));
+        self
+    }
+
+    /// Checks that `answer` has the right shape and fits the constraints.
+    pub fn validate(&self, answer: &Value) -> Result<(), String> {
+        self.validate_shape(answer)?;
+        if !self.checks.is_empty() {
+            let answer = self.convert(answer.clone())?;
+            for check in &self.checks {
+                (check.0)(&answer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_shape(&self, answer: &Value) -> Result<(), String> {
+        if let Some(element_question) = &self.elements {
+            let elements = match answer {
+                Value::String(input) => return self.parse_elements(input).map(|_| ()),
+                Value::Array(elements) => elements,
+                answer => return Err(format!("{answer} does not list any elements")),
+            };
+            for (i, element) in elements.iter().enumerate() {
+                element_question
+                    .validate(element)
+                    .map_err(|e| format!("element [{i}]: {e}"))?;
+            }
+            return self.constraints.check(answer);
+        }
+        if let Some(root_schema) = &self.json_schema {
+            let input = answer
+                .as_str()
+                .ok_or_else(|| format!("{answer} is not json text"))?;
+            if input.trim().is_empty() {
+                return Ok(());
+            }
+            let value = serde_json::from_str::<Value>(input).map_err(|e| e.to_string())?;
+            let value = match &self.restore {
+                Some(restore) => (restore.0)(value),
+                None => value,
+            };
+            return validate_value(&root_schema.definitions, &root_schema.schema, &value);
+        }
+        if let Some(columns) = &self.columns {
+            let records = match answer {
+                Value::String(path) => return self.parse_records(path).map(|_| ()),
+                Value::Array(records) => records,
+                answer => return Err(format!("{answer} does not list any records")),
+            };
+            for (i, record) in records.iter().enumerate() {
+                for column in columns {
+                    let value = record.get(&column.name).unwrap_or(&Value::Null);
+                    column
+                        .validate(value)
+                        .map_err(|e| format!("element [{i}], column {}: {e}", column.name))?;
+                }
+            }
+            return self.constraints.check(answer);
+        }
+        if let Some(input) = self.bytes {
+            let bytes = match answer {
+                Value::String(text) => byte_values(input.decode(text)?),
+                Value::Array(bytes)
+                    if bytes.iter().all(|x| x.as_u64().is_some_and(|x| x <= 255)) =>
+                {
+                    bytes.clone()
+                }
+                answer => return Err(format!("{answer} is not a list of bytes")),
+            };
+            return self.constraints.check(&Value::Array(bytes));
+        }
+        if let Some(parsed) = self.parse_date_time(answer) {
+            return self.constraints.check(&Value::String(parsed?));
+        }
+        let fits = match self.kind {
+            QuestionKind::Confirm | QuestionKind::Boolean => answer.is_boolean(),
+            QuestionKind::Select => answer
+                .as_str()
+                .is_some_and(|choice| self.choices.iter().any(|x| x == choice)),
+            QuestionKind::MultiSelect => {
+                let Some(selected) = answer.as_array() else {
+                    return Err(format!("{answer} is not a list of choices"));
+                };
+                for (i, choice) in selected.iter().enumerate() {
+                    if !choice
+                        .as_str()
+                        .is_some_and(|choice| self.choices.iter().any(|x| x == choice))
+                    {
+                        return Err(format!("{choice} is not one of the choices"));
+                    }
+                    if selected[..i].contains(choice) {
+                        return Err(format!("{choice} is selected more than once"));
+                    }
+                }
+                true
+            }
+            QuestionKind::String => answer.is_string(),
+            QuestionKind::Integer => answer.is_i64() || answer.is_u64(),
+            QuestionKind::Number => answer.is_number(),
+        };
+        if !fits {
+            return Err(format!(
+                "{answer} does not answer a {:?} question",
+                self.kind
+            ));
+        }
+        if let (Some(file_path), Some(input)) = (&self.file_path, answer.as_str()) {
+            file_path.check(input)?;
+        }
+        self.constraints.check(answer)
+    }
+
+    /// Validates `answer`, replacing text that lists several elements with
+    /// an array of them so that replaying the answer later does not depend on
+    /// a file that may have changed since.
+    pub(crate) fn normalize(&self, answer: Value) -> Result<Value, String> {
+        self.validate(&answer)?;
+        self.convert(answer)
+    }
+
+    fn convert(&self, answer: Value) -> Result<Value, String> {
+        match answer {
+            Value::String(input) if self.elements.is_some() => {
+                self.parse_elements(&input).map(Value::Array)
+            }
+            Value::String(path) if self.columns.is_some() => {
+                self.parse_records(&path).map(Value::Array)
+            }
+            Value::String(text) if self.bytes.is_some() => self
+                .bytes
+                .unwrap()
+                .decode(&text)
+                .map(byte_values)
+                .map(Value::Array),
+            Value::Array(elements) if self.elements.is_some() => {
+                let element_question = self.elements.as_ref().unwrap();
+                elements
+                    .into_iter()
+                    .map(|element| element_question.normalize(element))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Array)
+            }
+            answer => match self.parse_date_time(&answer) {
+                Some(parsed) => parsed.map(Value::String),
+                None => Ok(answer),
+            },
+        }
+    }
+
+    /// Reads the answer to a date or time question, which may be a shortcut
+    /// like `now` or `+1d`, into the string serde expects.
+    fn parse_date_time(&self, answer: &Value) -> Option<Result<String, String>> {
+        match (self.kind, &self.constraints.format, answer) {
+            (QuestionKind::String, Some(format), Value::String(input)) => {
+                datetime::parse(format, input)
+            }
+            _ => None,
+        }
+    }
+
+    /// Splits the answer to a question with [`Question::elements`] into the
+    /// elements it lists, reporting the index of the first bad element.
+    pub fn parse_elements(&self, input: &str) -> Result<Vec<Value>, String> {
+        let Some(element_question) = &self.elements else {
+            return Err("the question does not take several elements".to_string());
+        };
+        let text = match input.trim().strip_prefix('@') {
+            Some(path) => std::fs::read_to_string(path.trim())
+                .map_err(|e| format!("could not read {}: {e}", path.trim()))?,
+            None => input.to_string(),
+        };
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+        let mut elements = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| e.to_string())?;
+            for field in record.iter().filter(|field| !field.is_empty()) {
+                let element = element_question
+                    .parse(field)
+                    .map_err(|e| format!("element [{}]: {e}", elements.len()))?;
+                elements.push(element);
+            }
+        }
+        self.constraints.check(&Value::Array(elements.clone()))?;
+        Ok(elements)
+    }
+
+    /// Reads the CSV file at `path` for a question with
+    /// [`Question::columns`], reporting the row and column of the first bad
+    /// cell.
+    pub fn parse_records(&self, path: &str) -> Result<Vec<Value>, String> {
+        let Some(columns) = &self.columns else {
+            return Err("the question does not take records".to_string());
+        };
+        let path = path.trim();
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("could not read {path}: {e}"))?;
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+        let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+        if let Some(unknown) = headers
+            .iter()
+            .find(|header| columns.iter().all(|column| &column.name != header))
+        {
+            return Err(format!("unknown column {unknown}"));
+        }
+
+        let mut records = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| e.to_string())?;
+            let row = record.position().map_or(0, |position| position.line());
+            let mut object = Map::new();
+            for column in columns {
+                let cell = headers
+                    .iter()
+                    .position(|header| header == column.name)
+                    .and_then(|i| record.get(i));
+                let value = column
+                    .parse(cell)
+                    .map_err(|e| format!("row {row}, column {}: {e}", column.name))?;
+                object.insert(column.name.clone(), value);
+            }
+            records.push(Value::Object(object));
+        }
+        self.constraints.check(&Value::Array(records.clone()))?;
+        Ok(records)
+    }
+
+    /// Parses text typed by the user into an answer and validates it.
+    pub fn parse(&self, input: &str) -> Result<Value, String> {
+        let answer = match self.kind {
+            QuestionKind::Integer => input.trim().parse::<i64>().map(|x| json!(x)).ok(),
+            QuestionKind::Number => input.trim().parse::<f64>().map(|x| json!(x)).ok(),
+            QuestionKind::Confirm | QuestionKind::Boolean => {
+                input.trim().parse::<bool>().map(Value::Bool).ok()
+            }
+            QuestionKind::Select | QuestionKind::String => Some(Value::String(input.to_string())),
+            QuestionKind::MultiSelect => Some(Value::Array(
+                input
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|choice| !choice.is_empty())
+                    .map(|choice| Value::String(choice.to_string()))
+                    .collect(),
+            )),
+        };
+        match answer {
+            Some(answer) => self.normalize(answer),
+            None => Err(format!("{input:?} is not a valid {:?}", self.kind)),
+        }
+    }
+
+    pub fn accepts(&self, answer: &Value) -> bool {
+        self.validate(answer).is_ok()
+    }
+
+    /// How an answer is echoed back to the terminal.
+    pub(crate) fn answer_str(&self, answer: &Value) -> String {
+        match (self.kind, answer) {
+            _ if self.secret => secret::MASK.to_string(),
+            (QuestionKind::Confirm, Value::Bool(true)) => "Yes".to_string(),
+            (QuestionKind::Confirm, Value::Bool(false)) => "No".to_string(),
+            (_, Value::String(string)) => string.clone(),
+            (QuestionKind::MultiSelect, Value::Array(selected)) => selected
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(", "),
+            (_, answer) => answer.to_string(),
+        }
+    }
+}
+
+/// An inquire validator that checks answers with [`Question::validate`].
+fn validator<T: Into<Value> + Clone>(
+    question: &Question,
+) -> impl Fn(&T) -> Result<Validation, CustomUserError> + Clone {
+    let question = question.clone();
+    move |answer: &T| {
+        Ok(match question.validate(&answer.clone().into()) {
+            Ok(()) => Validation::Valid,
+            Err(message) => Validation::Invalid(ErrorMessage::Custom(message)),
+        })
+    }
+}
+
+/// The answer to a multi-select question with `selected` chosen.
+fn selected_json(selected: &[ListOption<&String>]) -> Value {
+    selected
+        .iter()
+        .map(|option| Value::String(option.value.clone()))
+        .collect()
+}
+
+/// Asks `question` on the terminal. `None` means the user hit `Esc`.
+pub(crate) fn prompt_terminal(question: &Question) -> SchemaResult<Option<Value>> {
+    let message = question.message.as_str();
+    let help = question.help.as_str();
+    let default = question.default.as_ref();
+    let examples = question
+        .examples
+        .iter()
+        .filter(|example| question.accepts(example))
+        .cloned()
+        .collect::<Vec<_>>();
+    if matches!(question.kind, QuestionKind::Integer | QuestionKind::Number) && !examples.is_empty()
+    {
+        let mut choices = examples.iter().map(Value::to_string).collect::<Vec<_>>();
+        choices.push("Something else".to_string());
+        let Some(choice) = Select::new(message, choices.clone())
+            .with_help_message(help)
+            .prompt_skippable()?
+        else {
+            return Ok(None);
+        };
+        if let Some(example) = choices
+            .iter()
+            .position(|x| *x == choice)
+            .and_then(|i| examples.get(i))
+        {
+            return Ok(Some(example.clone()));
+        }
+    }
+    let answer = match question.kind {
+        QuestionKind::Confirm => {
+            let mut prompt = Confirm::new(message).with_help_message(help);
+            if let Some(default) = default.and_then(Value::as_bool) {
+                prompt = prompt.with_default(default);
+            }
+            prompt.prompt_skippable()?.map(Value::Bool)
+        }
+        QuestionKind::Select => {
+            let mut prompt = Select::new(message, question.choices.clone()).with_help_message(help);
+            if let Some(default) = default.and_then(Value::as_str) {
+                if let Some(position) = question.choices.iter().position(|x| x == default) {
+                    prompt = prompt.with_starting_cursor(position);
+                }
+            }
+            prompt.prompt_skippable()?.map(Value::String)
+        }
+        QuestionKind::MultiSelect => {
+            let validator = validator::<Value>(question);
+            let defaults = default
+                .and_then(Value::as_array)
+                .map(|default| {
+                    question
+                        .choices
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, choice)| default.iter().any(|x| x == choice.as_str()))
+                        .map(|(i, _)| i)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let prompt = MultiSelect::new(message, question.choices.clone())
+                .with_help_message(help)
+                .with_default(&defaults)
+                .with_validator(move |selected: &[ListOption<&String>]| {
+                    validator(&selected_json(selected))
+                });
+            prompt
+                .prompt_skippable()?
+                .map(|selected| selected.into_iter().map(Value::String).collect())
+        }
+        QuestionKind::String if question.widget.as_deref() == Some("textarea") => {
+            let string_validator = validator::<String>(question);
+            let mut prompt = Editor::new(message)
+                .with_help_message(help)
+                .with_validator(move |answer: &str| string_validator(&answer.to_string()));
+            if question.json_schema.is_some() {
+                prompt = prompt.with_file_extension(".json");
+            }
+            if let Some(default) = default.and_then(Value::as_str) {
+                prompt = prompt.with_predefined_text(default);
+            }
+            prompt.prompt_skippable()?.map(Value::String)
+        }
+        QuestionKind::String if question.secret => {
+            let string_validator = validator::<String>(question);
+            Password::new(message)
+                .with_help_message(help)
+                .with_display_mode(PasswordDisplayMode::Masked)
+                .with_validator(move |answer: &str| string_validator(&answer.to_string()))
+                .prompt_skippable()?
+                .map(Value::String)
+        }
+        QuestionKind::String if question.widget.as_deref() == Some("date") => {
+            let string_validator = validator::<String>(question);
+            let mut prompt = DateSelect::new(message)
+                .with_help_message(help)
+                .with_validator(move |date: NaiveDate| {
+                    string_validator(&date.format("%Y-%m-%d").to_string())
+                });
+            if let Some(default) = default
+                .and_then(Value::as_str)
+                .and_then(|default| NaiveDate::parse_from_str(default, "%Y-%m-%d").ok())
+            {
+                prompt = prompt.with_default(default);
+            }
+            prompt
+                .prompt_skippable()?
+                .map(|date| Value::String(date.format("%Y-%m-%d").to_string()))
+        }
+        QuestionKind::String => {
+            let string_validator = validator::<String>(question);
+            let mut prompt = Text::new(message)
+                .with_help_message(help)
+                .with_validator(move |answer: &str| string_validator(&answer.to_string()));
+            let placeholder = match question.constraints.format.as_deref() {
+                Some("date-time") => Some("2024-12-31T09:30:00Z, now, +1d"),
+                Some("time") => Some("09:30, now, +1h"),
+                _ => None,
+            };
+            if let Some(placeholder) = placeholder {
+                prompt = prompt.with_placeholder(placeholder);
+            }
+            if let Some(file_path) = &question.file_path {
+                prompt = prompt.with_autocomplete(PathCompleter {
+                    file_path: file_path.clone(),
+                });
+            } else if !examples.is_empty() {
+                prompt = prompt.with_autocomplete(ListCompleter {
+                    choices: examples
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect(),
+                });
+            }
+            if let Some(default) = default.and_then(Value::as_str) {
+                prompt = prompt.with_default(default);
+            }
+            prompt.prompt_skippable()?.map(Value::String)
+        }
+        QuestionKind::Integer => {
+            let mut prompt = CustomType::<i64>::new(message)
+                .with_help_message(help)
+                .with_validator(validator(question));
+            if let Some(default) = default.and_then(Value::as_i64) {
+                prompt = prompt.with_default(default);
+            }
+            prompt.prompt_skippable()?.map(|x| json!(x))
+        }
+        QuestionKind::Number => {
+            let mut prompt = CustomType::<f64>::new(message)
+                .with_help_message(help)
+                .with_validator(validator(question));
+            if let Some(default) = default.and_then(Value::as_f64) {
+                prompt = prompt.with_default(default);
+            }
+            prompt.prompt_skippable()?.map(|x| json!(x))
+        }
+        QuestionKind::Boolean => {
+            let mut prompt = CustomType::<bool>::new(message).with_help_message(help);
+            if let Some(default) = default.and_then(Value::as_bool) {
+                prompt = prompt.with_default(default);
+            }
+            prompt.prompt_skippable()?.map(Value::Bool)
+        }
+    };
+    Ok(answer)
+}
@@ -0,0 +1,249 @@
+use std::{fmt::Display, fs, path::Path};
+
+use heck::ToKebabCase;
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    error::{from_value, SchemaError, SchemaResult},
+    secret,
+};
+
+/// A reproducible, non-interactive form of a parsed value.
+///
+/// After walking through the prompts, wrap the resulting value in a `Replay`
+/// to get a compact json one-liner, an answer file, or the equivalent
+/// `--flag value` arguments for a clap-based command line.
+///
+/// Given the value's schema, secrets are written as `********` in all of
+/// them, and have to be filled in again by hand.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    value: Value,
+    /// The value with its secrets masked, which is what gets written out.
+    redacted: Value,
+}
+
+impl Replay {
+    /// A replay of a value without a schema, which has no secrets to hide.
+    pub fn new(value: Value) -> Self {
+        Self {
+            redacted: value.clone(),
+            value,
+        }
+    }
+
+    /// A replay of a value parsed against `root_schema`, hiding its secrets.
+    pub fn with_schema(root_schema: &RootSchema, value: Value) -> Self {
+        Self {
+            redacted: secret::redact(&root_schema.definitions, &root_schema.schema, &value),
+            value,
+        }
+    }
+
+    /// A replay of a value of `T`, hiding its secrets.
+    pub fn for_type<T: JsonSchema>(value: Value) -> Self {
+        Self::with_schema(&schema_for!(T), value)
+    }
+
+    pub fn value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The value as a single line of json.
+    pub fn json(&self) -> String {
+        self.redacted.to_string()
+    }
+
+    /// Writes the value as pretty json so it can later be read back with
+    /// [`read_answer_file`].
+    pub fn write_answer_file(&self, path: impl AsRef<Path>) -> SchemaResult<()> {
+        let mut contents = serde_json::to_string_pretty(&self.redacted)
+            .map_err(|e| SchemaError::Generic(e.to_string()))?;
+        contents.push('\n');
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// The value as command line arguments, one `--flag value` pair per
+    /// field of the top level object.
+    ///
+    /// Field names are converted to kebab-case the way clap does by default,
+    /// so `HTTPPort` becomes `--http-port`.
+    /// `true` becomes a bare flag, `false` and `null` are omitted, arrays of
+    /// primitives repeat the flag for each element and anything nested is
+    /// passed as json.
+    pub fn cli_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let Value::Object(map) = &self.redacted else {
+            if !self.redacted.is_null() {
+                args.push(arg_str(&self.redacted));
+            }
+            return args;
+        };
+        for (name, value) in map {
+            let flag = format!("--{}", name.to_kebab_case());
+            match value {
+                Value::Null | Value::Bool(false) => {}
+                Value::Bool(true) => args.push(flag),
+                Value::Array(items) if items.iter().all(is_primitive) => {
+                    for item in items {
+                        args.push(flag.clone());
+                        args.push(arg_str(item));
+                    }
+                }
+                value => {
+                    args.push(flag);
+                    args.push(arg_str(value));
+                }
+            }
+        }
+        args
+    }
+}
+
+impl Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "json: {}", shell_quote(&self.json()))?;
+        let args = self
+            .cli_args()
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>();
+        write!(f, "args: {}", args.join(" "))
+    }
+}
+
+/// Reads a value previously written with [`Replay::write_answer_file`].
+///
+/// Secrets are written masked, so reading a file whose secrets have not
+/// been filled in fails with [`SchemaError::MaskedSecret`] rather than
+/// taking `********` for the secret.
+pub fn read_answer_file<T: JsonSchema + DeserializeOwned>(
+    path: impl AsRef<Path>,
+) -> SchemaResult<T> {
+    let contents = fs::read_to_string(path)?;
+    let value: Value =
+        serde_json::from_str(&contents).map_err(|e| SchemaError::Generic(e.to_string()))?;
+    let root_schema = schema_for!(T);
+    if let Some(path) = secret::find_masked(&root_schema.definitions, &root_schema.schema, &value) {
+        return Err(SchemaError::MaskedSecret { path });
+    }
+    from_value(&root_schema, value)
+}
+
+fn is_primitive(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+fn arg_str(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+".contains(c))
+    {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::JsonSchema;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::Replay;
+    use crate::error::SchemaError;
+
+    #[test]
+    fn test_cli_args() {
+        let replay = Replay::new(json!({
+            "my_int": 5,
+            "my_bool": true,
+            "other_bool": false,
+            "my_opt": null,
+            "myVec": [1, 2],
+            "my_enum": { "StructVariant": { "floats": [1.0] } },
+            "my_string": "hello world",
+            "HTTPPort": 80,
+        }));
+        assert_eq!(
+            replay.cli_args(),
+            vec![
+                "--http-port",
+                "80",
+                "--my-vec",
+                "1",
+                "--my-vec",
+                "2",
+                "--my-bool",
+                "--my-enum",
+                r#"{"StructVariant":{"floats":[1.0]}}"#,
+                "--my-int",
+                "5",
+                "--my-string",
+                "hello world",
+            ]
+        );
+        assert_eq!(
+            replay.to_string().lines().last().unwrap(),
+            r#"args: --http-port 80 --my-vec 1 --my-vec 2 --my-bool --my-enum '{"StructVariant":{"floats":[1.0]}}' --my-int 5 --my-string 'hello world'"#
+        );
+    }
+
+    #[test]
+    fn test_answer_file() {
+        let path = std::env::temp_dir().join(format!(
+            "interactive_parse_test_answer_file_{}.json",
+            std::process::id()
+        ));
+        let replay = Replay::new(json!({ "my_int": 5, "my_vec": [1, 2] }));
+        replay.write_answer_file(&path).unwrap();
+        let value: serde_json::Value = super::read_answer_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(&value, replay.value());
+        assert_eq!(replay.json(), r#"{"my_int":5,"my_vec":[1,2]}"#);
+    }
+
+    #[test]
+    fn test_secrets() {
+        #[derive(JsonSchema, Deserialize)]
+        #[allow(dead_code)]
+        struct Login {
+            user: String,
+            #[schemars(schema_with = "crate::secret::x_secret::<String>")]
+            token: String,
+        }
+
+        let value = json!({ "user": "ada", "token": "t0ken" });
+        let replay = Replay::for_type::<Login>(value.clone());
+        assert_eq!(replay.value(), &value);
+        assert_eq!(replay.json(), r#"{"token":"********","user":"ada"}"#);
+        assert_eq!(
+            replay.cli_args(),
+            vec!["--token", "********", "--user", "ada"]
+        );
+
+        let path = std::env::temp_dir().join(format!(
+            "interactive_parse_test_secrets_{}.json",
+            std::process::id()
+        ));
+        replay.write_answer_file(&path).unwrap();
+        let error = match super::read_answer_file::<Login>(&path) {
+            Err(SchemaError::MaskedSecret { path }) => path,
+            _ => panic!("the masked token was read back"),
+        };
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(error, "/token");
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::debug;
+use schemars::schema::RootSchema;
+use serde_json::{json, Value};
+
+use crate::error::SchemaResult;
+
+/// A file that the answers of an in progress parse are saved to, so that an
+/// interrupted parse can be resumed on the next run.
+#[derive(Debug, Clone)]
+pub(crate) struct SessionFile {
+    path: PathBuf,
+    fingerprint: String,
+}
+
+impl SessionFile {
+    pub(crate) fn new(path: &Path, root_schema: &RootSchema) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            fingerprint: schema_fingerprint(root_schema),
+        }
+    }
+
+    /// Loads the saved answers. Sessions saved against a different schema
+    /// are deleted rather than resumed.
+    pub(crate) fn load(&self) -> SchemaResult<Option<Vec<Value>>> {
+        let Ok(contents) = fs::read_to_string(&self.path) else {
+            return Ok(None);
+        };
+        let session = serde_json::from_str::<Value>(&contents).unwrap_or_default();
+        let answers = match session.get("fingerprint").and_then(Value::as_str) {
+            Some(fingerprint) if fingerprint == self.fingerprint => {
+                session.get("answers").and_then(Value::as_array).cloned()
+            }
+            _ => None,
+        };
+        if answers.is_none() {
+            debug!("discarding stale session file {:?}", self.path);
+            self.remove()?;
+        }
+        Ok(answers.filter(|answers| !answers.is_empty()))
+    }
+
+    pub(crate) fn save(&self, answers: &[Value]) -> SchemaResult<()> {
+        let session = json!({
+            "fingerprint": self.fingerprint,
+            "answers": answers,
+        });
+        fs::write(&self.path, session.to_string())?;
+        Ok(())
+    }
+
+    pub(crate) fn remove(&self) -> SchemaResult<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// A stable hash of the schema, used to tell whether a saved session still
+/// matches the type being parsed.
+pub fn schema_fingerprint(root_schema: &RootSchema) -> String {
+    let schema = serde_json::to_string(root_schema).unwrap_or_default();
+    // 64 bit FNV-1a, which unlike `DefaultHasher` is stable across builds.
+    let hash = schema.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::schema_for;
+    use serde_json::json;
+
+    use super::{schema_fingerprint, SessionFile};
+
+    #[test]
+    fn test_session_file() {
+        let path = std::env::temp_dir().join("interactive_parse_test_session.json");
+        let session = SessionFile::new(&path, &schema_for!(Vec<u32>));
+        session.save(&[json!(true), json!(5)]).unwrap();
+        assert_eq!(session.load().unwrap(), Some(vec![json!(true), json!(5)]));
+
+        // A different schema invalidates the session.
+        let other = SessionFile::new(&path, &schema_for!(Vec<i32>));
+        assert_eq!(other.load().unwrap(), None);
+        assert!(!path.exists());
+
+        assert_ne!(
+            schema_fingerprint(&schema_for!(u32)),
+            schema_fingerprint(&schema_for!(i32))
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use std::collections::BTreeMap;
+
+use schemars::{
+    schema::{RootSchema, Schema, SchemaObject, SingleOrVec},
+    schema_for, JsonSchema,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::{
+    error::{from_value, SchemaError, SchemaResult},
+    get_root_title,
+    options::ParseOptions,
+    parse_root, parse_schema, secret,
+    state::ParseState,
+};
+
+/// Parses a `T` and runs its `validator` rules, asking again on the terminal
+/// for the fields that break them until the value passes.
+pub(crate) fn parse_validated<T>(options: &ParseOptions) -> SchemaResult<T>
+where
+    T: JsonSchema + DeserializeOwned + Validate,
+{
+    check_frontend(options)?;
+    let root_schema = schema_for!(T);
+    let mut value = parse_root(root_schema.clone(), options)?;
+    loop {
+        let obj: T = from_value(&root_schema, value.clone())?;
+        let Err(errors) = obj.validate() else {
+            return Ok(obj);
+        };
+        let state = ParseState::new(&root_schema, options, None, Vec::new());
+        value = reenter(&state, &root_schema, value, &errors)?;
+    }
+}
+
+/// Fails if `options` pick a frontend other than the terminal, since the
+/// fields are asked for again on the terminal.
+fn check_frontend(options: &ParseOptions) -> SchemaResult<()> {
+    #[cfg(feature = "form")]
+    if options.form {
+        return Err(SchemaError::TerminalOnly { frontend: "form" });
+    }
+    #[cfg(feature = "web")]
+    if options.web_form.is_some() {
+        return Err(SchemaError::TerminalOnly {
+            frontend: "web form",
+        });
+    }
+    if options.json_rpc {
+        return Err(SchemaError::TerminalOnly {
+            frontend: "json-rpc frontend",
+        });
+    }
+    Ok(())
+}
+
+/// Asks again for every value of `value` that `errors` points at, leaving
+/// the rest as it is.
+fn reenter(
+    state: &ParseState,
+    root_schema: &RootSchema,
+    mut value: Value,
+    errors: &ValidationErrors,
+) -> SchemaResult<Value> {
+    let mut failures = BTreeMap::new();
+    collect(errors, "", &mut failures);
+
+    // `validator` names fields as they are in Rust, which serde may have
+    // renamed, so each pointer is looked up again in the entered value.
+    let mut located: BTreeMap<String, (Vec<String>, SchemaObject, String)> = BTreeMap::new();
+    let mut unlocated = Vec::new();
+    for (pointer, messages) in failures {
+        let segments = pointer
+            .split('/')
+            .skip(1)
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .collect::<Vec<_>>();
+        match locate(
+            &root_schema.definitions,
+            &root_schema.schema,
+            &value,
+            &segments,
+        ) {
+            Some((segments, schema)) => {
+                let pointer = to_pointer(&segments);
+                let message = message_at(&pointer, &messages);
+                located.insert(pointer, (segments, schema, message));
+            }
+            None => unlocated.push(message_at(&pointer, &messages)),
+        }
+    }
+    // A failure that can't be pinned on a value is fixed by entering the
+    // whole value again.
+    if !unlocated.is_empty() {
+        let message = located
+            .into_values()
+            .map(|(_, _, message)| message)
+            .chain(unlocated)
+            .collect::<Vec<_>>()
+            .join("; ");
+        located = BTreeMap::from([(
+            String::new(),
+            (Vec::new(), root_schema.schema.clone(), message),
+        )]);
+    }
+
+    let mut reentered: Vec<String> = Vec::new();
+    for (pointer, (segments, schema, message)) in located {
+        // Entering a value again also replaces everything inside it.
+        if reentered
+            .iter()
+            .any(|parent| pointer.starts_with(&format!("{parent}/")))
+        {
+            continue;
+        }
+        state.notify(format!("{message}, please try again"));
+        let name = segments.last().cloned().unwrap_or_default();
+        let entered = at_segments(state, &segments, || {
+            parse_schema(state, get_root_title(root_schema), name, schema)
+        })
+        .map_err(|e| match e {
+            SchemaError::Exit { path, .. } => SchemaError::Exit {
+                partial: secret::redact(&root_schema.definitions, &root_schema.schema, &value),
+                path,
+            },
+            e => e,
+        })?;
+        if let Some(target) = value.pointer_mut(&pointer) {
+            *target = entered;
+        }
+        reentered.push(pointer);
+    }
+    Ok(value)
+}
+
+fn to_pointer(segments: &[String]) -> String {
+    segments
+        .iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// `messages` about the value at `pointer`, as shown to the user.
+fn message_at(pointer: &str, messages: &[String]) -> String {
+    match pointer.is_empty() {
+        true => messages.join(", "),
+        false => format!("{pointer}: {}", messages.join(", ")),
+    }
+}
+
+/// Runs `f` with `segments` appended to the current path.
+fn at_segments<T>(
+    state: &ParseState,
+    segments: &[String],
+    f: impl FnOnce() -> SchemaResult<T>,
+) -> SchemaResult<T> {
+    match segments.split_first() {
+        Some((segment, rest)) => state.with_segment(segment, || at_segments(state, rest, f)),
+        None => f(),
+    }
+}
+
+/// Gathers the messages of `errors` by the json pointer of the value they
+/// are about, where `pointer` is the pointer of the value `errors` is for.
+fn collect(errors: &ValidationErrors, pointer: &str, failures: &mut BTreeMap<String, Vec<String>>) {
+    for (field, kind) in errors.errors() {
+        let field_pointer = match field.as_ref() {
+            // Rules on the struct itself, such as `#[validate(schema(...))]`.
+            "__all__" => pointer.to_string(),
+            field => format!("{pointer}/{}", field.replace('~', "~0").replace('/', "~1")),
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => failures
+                .entry(field_pointer)
+                .or_default()
+                .extend(errors.iter().map(describe)),
+            ValidationErrorsKind::Struct(errors) => collect(errors, &field_pointer, failures),
+            ValidationErrorsKind::List(elements) => {
+                for (i, errors) in elements {
+                    collect(errors, &format!("{field_pointer}/{i}"), failures);
+                }
+            }
+        }
+    }
+}
+
+/// The message of `error`, or a description of the rule it breaks.
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let mut params = error
+        .params
+        .iter()
+        .filter(|(name, _)| *name != "value")
+        .map(|(name, value)| format!("{name} {value}"))
+        .collect::<Vec<_>>();
+    params.sort();
+    match params.is_empty() {
+        true => format!("fails the {} check", error.code),
+        false => format!("fails the {} check ({})", error.code, params.join(", ")),
+    }
+}
+
+/// The segments of `segments` as they are named in `value`, whose schema
+/// is `schema`, along with the schema of the value they point at. Fields
+/// are matched by name, or failing that by a name that only differs in
+/// case, `_` and `-`, which is how serde's `rename_all` changes them.
+fn locate(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+    value: &Value,
+    segments: &[String],
+) -> Option<(Vec<String>, SchemaObject)> {
+    let Some((segment, rest)) = segments.split_first() else {
+        return Some((Vec::new(), schema.clone()));
+    };
+    if let Some(reference) = &schema.reference {
+        let name = reference
+            .strip_prefix("#/definitions/")
+            .unwrap_or(reference);
+        return locate(
+            definitions,
+            object(definitions.get(name)?)?,
+            value,
+            segments,
+        );
+    }
+
+    let (key, child) = match value {
+        Value::Object(map) => map.get_key_value(segment).or_else(|| {
+            map.iter()
+                .find(|(key, _)| normalize(key) == normalize(segment))
+        })?,
+        Value::Array(elements) => (segment, elements.get(segment.parse::<usize>().ok()?)?),
+        _ => return None,
+    };
+    let found = |schema: &Schema| {
+        let (mut segments, schema) = locate(definitions, object(schema)?, child, rest)?;
+        segments.insert(0, key.clone());
+        Some((segments, schema))
+    };
+    if let (Some(object_info), Value::Object(_)) = (&schema.object, value) {
+        let property = object_info
+            .properties
+            .get(key)
+            .or(object_info.additional_properties.as_deref());
+        if let Some(found) = property.and_then(found) {
+            return Some(found);
+        }
+    }
+    if let (Some(array_info), Value::Array(_)) = (&schema.array, value) {
+        let item = match &array_info.items {
+            Some(SingleOrVec::Single(item)) => Some(item.as_ref()),
+            Some(SingleOrVec::Vec(items)) => items.get(segment.parse::<usize>().ok()?),
+            None => None,
+        };
+        if let Some(found) = item.and_then(found) {
+            return Some(found);
+        }
+    }
+
+    // The value is one of the alternatives, so the first one that has the
+    // segment is the one it was entered as.
+    let subschemas = schema.subschemas.as_ref()?;
+    [&subschemas.all_of, &subschemas.any_of, &subschemas.one_of]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .find_map(|schema| locate(definitions, object(schema)?, value, segments))
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '_' | '-'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn object(schema: &Schema) -> Option<&SchemaObject> {
+    match schema {
+        Schema::Object(object) => Some(object),
+        Schema::Bool(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use schemars::{schema_for, JsonSchema};
+    use serde::Deserialize;
+    use serde_json::json;
+    use validator::Validate;
+
+    use super::{collect, parse_validated, reenter};
+    use crate::{error::SchemaError, options::ParseOptions, state::ParseState};
+
+    #[derive(JsonSchema, Deserialize, Validate, Debug)]
+    struct Address {
+        #[validate(length(min = 1, message = "must not be empty"))]
+        street: String,
+    }
+
+    #[derive(JsonSchema, Deserialize, Validate, Debug)]
+    #[allow(dead_code)]
+    struct Account {
+        #[validate(nested)]
+        address: Address,
+        #[validate(range(min = 18))]
+        age: u8,
+        #[validate(length(min = 3))]
+        name: String,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_reenter() {
+        let root_schema = schema_for!(Account);
+        let value = json!({
+            "address": { "street": "" },
+            "age": 12,
+            "name": "al",
+            "tags": ["admin"],
+        });
+        let errors = serde_json::from_value::<Account>(value.clone())
+            .unwrap()
+            .validate()
+            .unwrap_err();
+
+        let mut failures = BTreeMap::new();
+        collect(&errors, "", &mut failures);
+        assert_eq!(
+            failures,
+            BTreeMap::from([
+                (
+                    "/address/street".to_string(),
+                    vec!["must not be empty".to_string()]
+                ),
+                (
+                    "/age".to_string(),
+                    vec!["fails the range check (min 18)".to_string()]
+                ),
+                (
+                    "/name".to_string(),
+                    vec!["fails the length check (min 3)".to_string()]
+                ),
+            ])
+        );
+
+        let options = ParseOptions::new();
+        let answers = vec![json!("Main St"), json!(30), json!("alice")];
+        let state = ParseState::detached(&root_schema, &options, answers);
+        let value = reenter(&state, &root_schema, value, &errors).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "address": { "street": "Main St" },
+                "age": 30,
+                "name": "alice",
+                "tags": ["admin"],
+            })
+        );
+        let account = serde_json::from_value::<Account>(value).unwrap();
+        assert!(account.validate().is_ok());
+    }
+
+    #[derive(JsonSchema, Deserialize, Validate, Debug)]
+    #[serde(rename_all = "camelCase")]
+    struct Profile {
+        #[validate(length(min = 3))]
+        display_name: String,
+        #[serde(rename = "mail")]
+        #[validate(length(min = 1, message = "must not be empty"))]
+        email_address: String,
+    }
+
+    #[test]
+    fn test_renamed_fields() {
+        let root_schema = schema_for!(Profile);
+        let options = ParseOptions::new();
+        let errors = |value| {
+            serde_json::from_value::<Profile>(value)
+                .unwrap()
+                .validate()
+                .unwrap_err()
+        };
+
+        // `rename_all` is undone to find the field.
+        let value = json!({ "displayName": "al", "mail": "al@example.com" });
+        let state = ParseState::detached(&root_schema, &options, vec![json!("alice")]);
+        let value = reenter(&state, &root_schema, value.clone(), &errors(value)).unwrap();
+        assert_eq!(
+            value,
+            json!({ "displayName": "alice", "mail": "al@example.com" })
+        );
+
+        // A field renamed outright can't be found, so the whole value is
+        // asked for again.
+        let value = json!({ "displayName": "alice", "mail": "" });
+        let state = ParseState::detached(&root_schema, &options, Vec::new());
+        assert!(matches!(
+            reenter(&state, &root_schema, value.clone(), &errors(value)),
+            Err(SchemaError::Unanswered)
+        ));
+        let question = state.take_pending().unwrap();
+        assert_eq!(question.path, "/displayName");
+        assert!(question
+            .help
+            .starts_with("/email_address: must not be empty, please try again"));
+    }
+
+    #[test]
+    fn test_terminal_only() {
+        let options = ParseOptions::new().with_json_rpc();
+        assert!(matches!(
+            parse_validated::<Profile>(&options),
+            Err(SchemaError::TerminalOnly { .. })
+        ));
+    }
+}
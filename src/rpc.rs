@@ -0,0 +1,372 @@
+//! Drives a [`Session`] over JSON-RPC 2.0, one message per line, so that an
+//! editor extension or any other process can host the prompts.
+//!
+//! Whenever a new question is waiting, a `question` notification is sent
+//! with the question and everything answered so far:
+//!
+//! ```json
+//! {"jsonrpc":"2.0","method":"question","params":{"path":"/name","kind":"string",...,"partial":{}}}
+//! ```
+//!
+//! The host replies with requests:
+//!
+//! - `answer` with `{"value": ...}`, or `{"default": true}` to accept the
+//!   question's default.
+//! - `back` to take back the last answer.
+//! - `cancel` to stop, which ends the parse with [`SchemaError::Exit`].
+//!
+//! Requests with an `id` get a response, with an error if the answer was
+//! rejected. Once every question has been answered, a `done` notification
+//! carries the value.
+//!
+//! Secrets are masked as `********` in every message, including the `done`
+//! value, so they never leave the process once answered. The value returned
+//! by [`serve`] holds them as entered.
+
+use std::io::{BufRead, Write};
+
+use log::debug;
+use serde_json::{json, Value};
+
+use crate::{
+    error::{SchemaError, SchemaResult},
+    prompt::{ByteInput, Question, QuestionKind},
+    session::{Answer, Session},
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Runs `session` over JSON-RPC, reading requests from `input` and writing
+/// responses and notifications to `output`. Returns the completed value, or
+/// [`SchemaError::Exit`] if the host cancels or closes `input`.
+pub fn serve(
+    mut session: Session,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> SchemaResult<Value> {
+    let mut lines = input.lines();
+    loop {
+        let Some(question) = session.next_question() else {
+            let value = session.partial();
+            notify(
+                &mut output,
+                "done",
+                json!({ "value": session.redact(&value) }),
+            )?;
+            return Ok(value);
+        };
+        let mut params = question_json(&question);
+        params["partial"] = session.redact(&session.partial());
+        notify(&mut output, "question", params)?;
+
+        // Read requests until one of them moves the session on.
+        loop {
+            let Some(line) = lines.next().transpose()? else {
+                return Err(exit(&session, &question));
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request = match serde_json::from_str::<Value>(&line) {
+                Ok(request) => request,
+                Err(e) => {
+                    respond(&mut output, &Value::Null, Err((PARSE_ERROR, e.to_string())))?;
+                    continue;
+                }
+            };
+            let id = request.get("id").cloned().unwrap_or(Value::Null);
+            let params = request.get("params").cloned().unwrap_or(Value::Null);
+            let (result, moved) = match request.get("method").and_then(Value::as_str) {
+                Some("answer") => match answer_param(&params) {
+                    Some(answer) => match session.answer(answer) {
+                        Ok(()) => (Ok(Value::Null), true),
+                        Err(SchemaError::InvalidAnswer { message, .. }) => {
+                            (Err((INVALID_PARAMS, message)), false)
+                        }
+                        Err(e) => return Err(e),
+                    },
+                    None => (
+                        Err((
+                            INVALID_PARAMS,
+                            "expected {\"value\": ...} or {\"default\": true}".to_string(),
+                        )),
+                        false,
+                    ),
+                },
+                Some("back") => {
+                    let moved = session.back()?;
+                    (Ok(Value::Bool(moved)), moved)
+                }
+                Some("cancel") => {
+                    if !id.is_null() {
+                        respond(&mut output, &id, Ok(Value::Null))?;
+                    }
+                    return Err(exit(&session, &question));
+                }
+                Some(method) => (
+                    Err((METHOD_NOT_FOUND, format!("unknown method {method:?}"))),
+                    false,
+                ),
+                None => (Err((INVALID_REQUEST, "missing method".to_string())), false),
+            };
+            if !id.is_null() {
+                respond(&mut output, &id, result)?;
+            } else if let Err((_, message)) = result {
+                debug!("rejected notification: {message}");
+            }
+            if moved {
+                break;
+            }
+        }
+    }
+}
+
+fn answer_param(params: &Value) -> Option<Answer> {
+    if params.get("default") == Some(&Value::Bool(true)) {
+        return Some(Answer::Default);
+    }
+    params.get("value").cloned().map(Answer::Value)
+}
+
+fn exit(session: &Session, question: &Question) -> SchemaError {
+    SchemaError::Exit {
+        partial: session.redact(&session.partial()),
+        path: question.path.clone(),
+    }
+}
+
+/// The json form of a question sent to the host.
+fn question_json(question: &Question) -> Value {
+    let kind = match question.kind {
+        QuestionKind::Confirm => "confirm",
+        QuestionKind::Select => "select",
+        QuestionKind::MultiSelect => "multiselect",
+        QuestionKind::String => "string",
+        QuestionKind::Integer => "integer",
+        QuestionKind::Number => "number",
+        QuestionKind::Boolean => "boolean",
+    };
+    let constraints = &question.constraints;
+    json!({
+        "path": question.path,
+        "kind": kind,
+        "message": question.message,
+        "help": question.help,
+        "description": question.description,
+        "choices": question.choices,
+        "default": question.default,
+        "examples": question.examples,
+        "widget": question.widget,
+        "secret": question.secret,
+        "filePath": question.file_path.as_ref().map(|file_path| json!({
+            "mustExist": file_path.must_exist,
+            "directory": file_path.directory,
+            "extensions": file_path.extensions,
+        })),
+        "bytes": question.bytes.map(|input| match input {
+            ByteInput::Hex => "hex",
+            ByteInput::Base64 => "base64",
+            ByteInput::Text => "text",
+            ByteInput::File => "file",
+        }),
+        "constraints": {
+            "minimum": constraints.minimum,
+            "maximum": constraints.maximum,
+            "exclusiveMinimum": constraints.exclusive_minimum,
+            "exclusiveMaximum": constraints.exclusive_maximum,
+            "multipleOf": constraints.multiple_of,
+            "minLength": constraints.min_length,
+            "maxLength": constraints.max_length,
+            "pattern": constraints.pattern,
+            "format": constraints.format,
+            "minItems": constraints.min_items,
+            "maxItems": constraints.max_items,
+            "uniqueItems": constraints.unique_items,
+        },
+        "elements": question.elements.as_deref().map(question_json),
+        "jsonSchema": question.json_schema,
+        "columns": question.columns.as_ref().map(|columns| {
+            columns
+                .iter()
+                .map(|column| {
+                    json!({
+                        "name": column.name,
+                        "question": question_json(&column.question),
+                        "optional": column.optional,
+                    })
+                })
+                .collect::<Vec<_>>()
+        }),
+    })
+}
+
+fn notify(output: &mut impl Write, method: &str, params: Value) -> SchemaResult<()> {
+    send(
+        output,
+        json!({ "jsonrpc": "2.0", "method": method, "params": params }),
+    )
+}
+
+fn respond(
+    output: &mut impl Write,
+    id: &Value,
+    result: Result<Value, (i64, String)>,
+) -> SchemaResult<()> {
+    let message = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    };
+    send(output, message)
+}
+
+fn send(output: &mut impl Write, message: Value) -> SchemaResult<()> {
+    writeln!(output, "{message}")?;
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{schema_for, JsonSchema};
+    use serde_json::{json, Value};
+
+    use super::serve;
+    use crate::{error::SchemaError, session::Session, ParseOptions};
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Job {
+        name: String,
+        retries: u8,
+    }
+
+    fn messages(output: &[u8]) -> Vec<Value> {
+        String::from_utf8_lossy(output)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_rpc() {
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"answer","params":{"value":"build"}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"answer","params":{"value":300}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"back"}"#,
+            r#"{"jsonrpc":"2.0","id":4,"method":"answer","params":{"value":"deploy"}}"#,
+            r#"{"jsonrpc":"2.0","method":"answer","params":{"value":3}}"#,
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        let value = serve(
+            Session::for_type::<Job>().unwrap(),
+            input.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(value, json!({ "name": "deploy", "retries": 3 }));
+
+        let messages = messages(&output);
+        assert_eq!(messages[0]["method"], "question");
+        assert_eq!(messages[0]["params"]["path"], "/name");
+        assert_eq!(
+            messages[1],
+            json!({ "jsonrpc": "2.0", "id": 1, "result": null })
+        );
+        assert_eq!(messages[2]["params"]["path"], "/retries");
+        assert_eq!(messages[2]["params"]["partial"], json!({ "name": "build" }));
+        assert_eq!(messages[2]["params"]["constraints"]["maximum"], 255.0);
+        assert_eq!(messages[3]["error"]["code"], -32602);
+        assert_eq!(messages[4]["result"], true);
+        assert_eq!(messages[5]["params"]["path"], "/name");
+        assert_eq!(messages.last().unwrap()["method"], "done");
+
+        // Closing the input exits with what was answered so far.
+        let input = r#"{"jsonrpc":"2.0","id":1,"method":"answer","params":{"value":"build"}}"#;
+        let result = serve(
+            Session::for_type::<Job>().unwrap(),
+            input.as_bytes(),
+            &mut Vec::new(),
+        );
+        assert!(matches!(
+            result,
+            Err(SchemaError::Exit { partial, path })
+                if partial == json!({ "name": "build" }) && path == "/retries"
+        ));
+    }
+
+    #[test]
+    fn test_secrets() {
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        struct Login {
+            user: String,
+            #[schemars(skip_serializing)]
+            password: String,
+        }
+
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"answer","params":{"value":"hunter2"}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"answer","params":{"value":"ada"}}"#,
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        let value = serve(
+            Session::for_type::<Login>().unwrap(),
+            input.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(value, json!({ "user": "ada", "password": "hunter2" }));
+        let done = messages(&output).pop().unwrap();
+        assert_eq!(done["method"], "done");
+        assert_eq!(
+            done["params"]["value"],
+            json!({ "user": "ada", "password": "********" })
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let options = ParseOptions::new().with_validator("/retries", |retries, _| {
+            match retries.as_u64() > Some(5) {
+                true => Err("at most 5 retries are allowed".to_string()),
+                false => Ok(()),
+            }
+        });
+        let input = [
+            r#"{"jsonrpc":"2.0","id":1,"method":"answer","params":{"value":"build"}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"answer","params":{"value":9}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"answer","params":{"value":3}}"#,
+        ]
+        .join("\n");
+        let mut output = Vec::new();
+        let value = serve(
+            Session::with_options(schema_for!(Job), options.clone()).unwrap(),
+            input.as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(value, json!({ "name": "build", "retries": 3 }));
+        assert_eq!(
+            messages(&output)[3]["error"]["message"],
+            "at most 5 retries are allowed"
+        );
+
+        let options = options.with_json_rpc().with_session_file("unused.json");
+        assert!(matches!(
+            crate::parse_root(schema_for!(Job), &options),
+            Err(SchemaError::UnsupportedOption {
+                option: "session files",
+                ..
+            })
+        ));
+    }
+}
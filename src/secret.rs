@@ -0,0 +1,203 @@
+use schemars::schema::{Schema, SchemaObject, SingleOrVec};
+use serde_json::Value;
+
+/// What a secret is shown as.
+pub(crate) const MASK: &str = "********";
+
+/// Whether values of `schema` are secrets, which is the case for
+/// `"format": "password"`, `"writeOnly": true` and `"x-secret": true`.
+pub(crate) fn is_secret(schema: &SchemaObject) -> bool {
+    schema.format.as_deref() == Some("password")
+        || schema
+            .metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.write_only)
+        || schema.extensions.get("x-secret") == Some(&Value::Bool(true))
+}
+
+/// `value` with every secret in it replaced by [`MASK`].
+pub(crate) fn redact(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+    value: &Value,
+) -> Value {
+    let mut redacted = value.clone();
+    for pointer in secret_pointers(definitions, schema, value) {
+        if let Some(secret) = redacted.pointer_mut(&pointer) {
+            *secret = Value::String(MASK.to_string());
+        }
+    }
+    redacted
+}
+
+/// Puts the secrets of `original` back into `edited`, a copy of the
+/// redacted value, wherever they were left masked.
+pub(crate) fn unredact(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+    mut edited: Value,
+    original: &Value,
+) -> Value {
+    for pointer in secret_pointers(definitions, schema, &edited.clone()) {
+        if let (Some(secret), Some(value)) =
+            (edited.pointer_mut(&pointer), original.pointer(&pointer))
+        {
+            if secret.as_str() == Some(MASK) {
+                *secret = value.clone();
+            }
+        }
+    }
+    edited
+}
+
+/// The json pointer of the first secret in `value` that is still masked,
+/// such as one read back from an answer file.
+pub(crate) fn find_masked(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+    value: &Value,
+) -> Option<String> {
+    secret_pointers(definitions, schema, value)
+        .into_iter()
+        .find(|pointer| value.pointer(pointer).and_then(Value::as_str) == Some(MASK))
+}
+
+/// The json pointers of the secrets in `value`.
+pub(crate) fn secret_pointers(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+    value: &Value,
+) -> Vec<String> {
+    let mut pointers = Vec::new();
+    Finder {
+        definitions,
+        pointers: &mut pointers,
+    }
+    .object(schema, value, String::new());
+    pointers
+}
+
+struct Finder<'a> {
+    definitions: &'a schemars::Map<String, Schema>,
+    pointers: &'a mut Vec<String>,
+}
+
+impl Finder<'_> {
+    fn schema(&mut self, schema: &Schema, value: &Value, path: String) {
+        if let Schema::Object(schema) = schema {
+            self.object(schema, value, path);
+        }
+    }
+
+    fn object(&mut self, schema: &SchemaObject, value: &Value, path: String) {
+        if value.is_null() {
+            return;
+        }
+        if is_secret(schema) {
+            self.pointers.push(path);
+            return;
+        }
+        if let Some(reference) = &schema.reference {
+            let name = reference
+                .strip_prefix("#/definitions/")
+                .unwrap_or(reference);
+            if let Some(definition) = self.definitions.get(name) {
+                self.schema(definition, value, path.clone());
+            }
+        }
+
+        if let (Some(array_info), Value::Array(elements)) = (&schema.array, value) {
+            match &array_info.items {
+                Some(SingleOrVec::Single(item)) => {
+                    for (i, element) in elements.iter().enumerate() {
+                        self.schema(item, element, format!("{path}/{i}"));
+                    }
+                }
+                Some(SingleOrVec::Vec(items)) => {
+                    for (i, (item, element)) in items.iter().zip(elements).enumerate() {
+                        self.schema(item, element, format!("{path}/{i}"));
+                    }
+                }
+                None => {}
+            }
+        }
+
+        if let (Some(object_info), Value::Object(map)) = (&schema.object, value) {
+            for (key, property) in map {
+                let property_path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                if let Some(schema) = object_info
+                    .properties
+                    .get(key)
+                    .or(object_info.additional_properties.as_deref())
+                {
+                    self.schema(schema, property, property_path);
+                }
+            }
+        }
+
+        // Secrets in any of the alternatives are hidden, which at worst hides
+        // a little too much.
+        if let Some(subschemas) = &schema.subschemas {
+            for schema in [&subschemas.all_of, &subschemas.any_of, &subschemas.one_of]
+                .into_iter()
+                .flatten()
+                .flatten()
+            {
+                self.schema(schema, value, path.clone());
+            }
+        }
+    }
+}
+
+/// The schema of `T` marked as a secret, for `schema_with` in tests.
+#[cfg(test)]
+pub(crate) fn x_secret<T: schemars::JsonSchema>(
+    gen: &mut schemars::gen::SchemaGenerator,
+) -> Schema {
+    let mut schema = T::json_schema(gen).into_object();
+    schema
+        .extensions
+        .insert("x-secret".to_string(), Value::Bool(true));
+    schema.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{schema_for, JsonSchema};
+    use serde_json::json;
+
+    use super::{redact, unredact};
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Login {
+        user: String,
+        #[schemars(schema_with = "super::x_secret::<Vec<String>>")]
+        tokens: Vec<String>,
+        #[schemars(schema_with = "password")]
+        password: String,
+    }
+
+    fn password(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        let mut schema = gen.subschema_for::<String>().into_object();
+        schema.format = Some("password".to_string());
+        schema.into()
+    }
+
+    #[test]
+    fn test_redact() {
+        let root = schema_for!(Login);
+        let value = json!({ "user": "ada", "tokens": ["a1"], "password": "hunter2" });
+        let redacted = redact(&root.definitions, &root.schema, &value);
+        assert_eq!(
+            redacted,
+            json!({ "user": "ada", "tokens": "********", "password": "********" })
+        );
+        let mut edited = redacted.clone();
+        edited["user"] = json!("bob");
+        assert_eq!(
+            unredact(&root.definitions, &root.schema, edited, &value),
+            json!({ "user": "bob", "tokens": ["a1"], "password": "hunter2" })
+        );
+    }
+}
@@ -0,0 +1,773 @@
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    error::{from_value, SchemaError, SchemaResult},
+    options::ParseOptions,
+    parse_root_value,
+    prompt::Question,
+    secret,
+    state::ParseState,
+};
+
+/// An answer to the current [`Question`] of a [`Session`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Answer {
+    Value(Value),
+    /// Accept the question's default.
+    Default,
+}
+
+impl From<Value> for Answer {
+    fn from(value: Value) -> Self {
+        Answer::Value(value)
+    }
+}
+
+enum Step {
+    Question {
+        question: Box<Question>,
+        partial: Value,
+    },
+    Done(Value),
+}
+
+/// Walks a schema one question at a time without owning the terminal, so
+/// GUIs, web handlers, chat bots and tests can drive the same traversal as
+/// the terminal prompts.
+///
+/// Every step replays the answers given so far through that traversal,
+/// stopping at the first question that has not been answered yet. Choice
+/// providers and validators in the options therefore run again at every
+/// step. If one of them changes its mind, the first answer that no longer
+/// fits is dropped along with every answer after it, and its question is
+/// asked again.
+///
+/// ```
+/// use interactive_parse::{Session, QuestionKind};
+/// use serde_json::json;
+///
+/// let mut session = Session::for_type::<Vec<u32>>().unwrap();
+/// while let Some(question) = session.next_question() {
+///     let answer = match question.kind {
+///         // Add a single element, then finish the array.
+///         QuestionKind::Select if session.partial().is_null() => json!("Add element"),
+///         QuestionKind::Select => json!("Done"),
+///         _ => json!(5),
+///     };
+///     session.answer(answer).unwrap();
+/// }
+/// assert_eq!(session.into_obj::<Vec<u32>>().unwrap(), vec![5]);
+/// ```
+pub struct Session {
+    root_schema: RootSchema,
+    options: ParseOptions,
+    answers: Vec<Value>,
+    step: Step,
+}
+
+impl Session {
+    pub fn new(root_schema: RootSchema) -> SchemaResult<Self> {
+        Self::with_options(root_schema, ParseOptions::default())
+    }
+
+    /// A session that asks its questions the way `options` asks for. Options
+    /// that pick a different frontend, such as a session file, are ignored.
+    pub fn with_options(root_schema: RootSchema, options: ParseOptions) -> SchemaResult<Self> {
+        options.check_prompts(&root_schema)?;
+        options.check_choices(&root_schema)?;
+        let mut session = Self {
+            root_schema,
+            options,
+            answers: Vec::new(),
+            step: Step::Done(Value::Null),
+        };
+        session.walk()?;
+        Ok(session)
+    }
+
+    pub fn for_type<T: JsonSchema>() -> SchemaResult<Self> {
+        Self::new(schema_for!(T))
+    }
+
+    /// The question waiting to be answered, or `None` once the value is
+    /// complete.
+    pub fn next_question(&self) -> Option<Question> {
+        match &self.step {
+            Step::Question { question, .. } => Some(*question.clone()),
+            Step::Done(_) => None,
+        }
+    }
+
+    /// Answers the current question. Answers that do not fit the question,
+    /// or that complete a value that is rejected as a whole, such as a
+    /// repeated element of a set, are rejected with
+    /// [`SchemaError::InvalidAnswer`], leaving the session unchanged.
+    ///
+    /// If the question changed once it was asked again, such as a choice
+    /// that is no longer offered, the answer is rejected the same way and
+    /// the new question is waiting instead.
+    pub fn answer(&mut self, answer: impl Into<Answer>) -> SchemaResult<()> {
+        let Step::Question { question, .. } = &self.step else {
+            return Err(SchemaError::InvalidAnswer {
+                path: String::new(),
+                message: "no question is waiting to be answered".to_string(),
+            });
+        };
+        let answer = match answer.into() {
+            Answer::Value(value) => value,
+            Answer::Default => {
+                question
+                    .default
+                    .clone()
+                    .ok_or_else(|| SchemaError::InvalidAnswer {
+                        path: question.path.clone(),
+                        message: "the question has no default".to_string(),
+                    })?
+            }
+        };
+        let answer = question
+            .normalize(answer)
+            .map_err(|message| SchemaError::InvalidAnswer {
+                path: question.path.clone(),
+                message,
+            })?;
+        self.answers.push(answer);
+        let count = self.answers.len();
+        self.walk()?;
+        match &self.step {
+            Step::Question { question, .. } if self.answers.len() < count => {
+                Err(SchemaError::InvalidAnswer {
+                    path: question.path.clone(),
+                    message: "the question changed, please answer it again".to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Takes back the last answer. Returns `false` if there was none.
+    pub fn back(&mut self) -> SchemaResult<bool> {
+        if self.answers.pop().is_none() {
+            return Ok(false);
+        }
+        self.walk()?;
+        Ok(true)
+    }
+
+    /// Every answer given so far, in order.
+    pub fn answers(&self) -> &[Value] {
+        &self.answers
+    }
+
+    /// The completed value, once every question has been answered.
+    pub fn value(&self) -> Option<&Value> {
+        match &self.step {
+            Step::Done(value) => Some(value),
+            Step::Question { .. } => None,
+        }
+    }
+
+    /// Deserializes the completed value.
+    pub fn into_obj<T: DeserializeOwned>(self) -> SchemaResult<T> {
+        let Step::Done(value) = self.step else {
+            return Err(SchemaError::Generic(
+                "the session has unanswered questions".to_string(),
+            ));
+        };
+        from_value(&self.root_schema, value)
+    }
+
+    /// Everything answered so far, assembled into a single value.
+    pub fn partial(&self) -> Value {
+        match &self.step {
+            Step::Question { partial, .. } => partial.clone(),
+            Step::Done(value) => value.clone(),
+        }
+    }
+
+    /// `value` with the secrets in it masked, for showing outside the
+    /// program.
+    pub(crate) fn redact(&self, value: &Value) -> Value {
+        secret::redact(
+            &self.root_schema.definitions,
+            &self.root_schema.schema,
+            value,
+        )
+    }
+
+    /// Replays the answers to find the next question. Answers past the
+    /// first one that doesn't fit its question any more are dropped, and so
+    /// is an answer that completes a rejected value, in which case the
+    /// rejection is returned once the session is back on its feet.
+    fn walk(&mut self) -> SchemaResult<()> {
+        let mut rejection = None;
+        loop {
+            let state =
+                ParseState::detached(&self.root_schema, &self.options, self.answers.clone());
+            let result = parse_root_value(&state, &self.root_schema);
+            let used = state.replayed();
+            self.step = match result {
+                Ok(value) => Step::Done(value),
+                Err(SchemaError::Unanswered) => Step::Question {
+                    question: Box::new(state.take_pending().ok_or_else(|| {
+                        SchemaError::Generic("the walk stopped without a question".to_string())
+                    })?),
+                    partial: state.partial(),
+                },
+                // The last answer used completed the rejected value.
+                Err(e @ SchemaError::InvalidAnswer { .. }) if used > 0 => {
+                    self.answers.truncate(used - 1);
+                    rejection = Some(e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            self.answers.truncate(used);
+            return rejection.map_or(Ok(()), Err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use schemars::{
+        gen::SchemaGenerator,
+        schema::{Schema, SchemaObject},
+        schema_for, JsonSchema,
+    };
+    use serde::Deserialize;
+    use serde_json::{json, Value};
+
+    use super::{Answer, Session};
+    use crate::{error::SchemaError, InteractivePrompt, ParseOptions, Question, QuestionKind};
+
+    #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Fast,
+        Slow { delay: u8 },
+    }
+
+    #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+    struct Job {
+        /// The name of the job.
+        #[serde(default = "default_name")]
+        name: String,
+        mode: Mode,
+        retries: Option<u32>,
+    }
+
+    fn default_name() -> String {
+        "job".to_string()
+    }
+
+    #[test]
+    fn test_session() {
+        let mut session = Session::for_type::<Job>().unwrap();
+
+        let question = session.next_question().unwrap();
+        assert_eq!(question.path, "/mode");
+        assert_eq!(question.choices, vec!["Fast", "Slow"]);
+        session.answer(json!("Slow")).unwrap();
+
+        let question = session.next_question().unwrap();
+        assert_eq!(question.path, "/mode/Slow/delay");
+        assert!(matches!(
+            session.answer(json!(300)),
+            Err(SchemaError::InvalidAnswer { .. })
+        ));
+        session.answer(json!(30)).unwrap();
+        assert_eq!(
+            session.partial(),
+            json!({ "mode": { "Slow": { "delay": 30 } } })
+        );
+
+        // Go back and pick the other variant instead.
+        assert!(session.back().unwrap());
+        assert!(session.back().unwrap());
+        session.answer(json!("Fast")).unwrap();
+
+        let question = session.next_question().unwrap();
+        assert_eq!(question.path, "/name");
+        assert_eq!(question.kind, QuestionKind::String);
+        assert_eq!(
+            question.description.as_deref(),
+            Some("The name of the job.")
+        );
+        assert_eq!(question.default, Some(json!("job")));
+        session.answer(Answer::Default).unwrap();
+
+        assert_eq!(session.next_question().unwrap().path, "/retries");
+        session.answer(json!(false)).unwrap();
+
+        assert!(session.next_question().is_none());
+        assert_eq!(
+            session.into_obj::<Job>().unwrap(),
+            Job {
+                name: "job".to_string(),
+                mode: Mode::Fast,
+                retries: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_json_entry() {
+        let options = ParseOptions::new().with_json_entry();
+        let mut session = Session::with_options(schema_for!(Job), options).unwrap();
+
+        let question = session.next_question().unwrap();
+        assert_eq!(question.path, "");
+        assert!(question.json_schema.is_some());
+        assert!(matches!(
+            session.answer(json!(r#"{"mode": {"Slow": {"delay": 300}}}"#)),
+            Err(SchemaError::InvalidAnswer { message, .. })
+                if message == "/mode/Slow/delay: must be at most 255"
+        ));
+        // Leaving it empty falls back to prompting for each field.
+        session.answer(json!("")).unwrap();
+
+        assert_eq!(session.next_question().unwrap().path, "/mode");
+        session.answer(json!(r#"{"Slow": {"delay": 30}}"#)).unwrap();
+        assert_eq!(session.next_question().unwrap().path, "/name");
+        session.answer(Answer::Default).unwrap();
+        session.answer(json!(false)).unwrap();
+        assert_eq!(
+            session.into_obj::<Job>().unwrap(),
+            Job {
+                name: "job".to_string(),
+                mode: Mode::Slow { delay: 30 },
+                retries: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_final_edit() {
+        fn textarea(gen: &mut SchemaGenerator) -> Schema {
+            let mut schema = String::json_schema(gen).into_object();
+            schema.format = Some("textarea".to_string());
+            schema.into()
+        }
+
+        #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+        struct Note {
+            #[schemars(schema_with = "textarea")]
+            text: String,
+            pinned: bool,
+        }
+
+        let options = ParseOptions::new().with_final_edit();
+        let mut session = Session::with_options(schema_for!(Note), options).unwrap();
+        session.answer(json!(false)).unwrap();
+        let question = session.next_question().unwrap();
+        assert_eq!(question.widget.as_deref(), Some("textarea"));
+        session.answer(json!("first line\nsecond line")).unwrap();
+
+        let question = session.next_question().unwrap();
+        assert_eq!(question.widget.as_deref(), Some("textarea"));
+        assert_eq!(
+            question.default,
+            Some(json!(
+                "{\n  \"pinned\": false,\n  \"text\": \"first line\\nsecond line\"\n}"
+            ))
+        );
+        assert!(matches!(
+            session.answer(json!(r#"{"pinned": "yes", "text": ""}"#)),
+            Err(SchemaError::InvalidAnswer { .. })
+        ));
+        session
+            .answer(json!(r#"{"pinned": true, "text": "edited"}"#))
+            .unwrap();
+        assert_eq!(
+            session.into_obj::<Note>().unwrap(),
+            Note {
+                text: "edited".to_string(),
+                pinned: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_secret() {
+        fn password(gen: &mut SchemaGenerator) -> Schema {
+            let mut schema = String::json_schema(gen).into_object();
+            schema.format = Some("password".to_string());
+            schema.into()
+        }
+
+        #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+        struct Login {
+            #[schemars(schema_with = "password")]
+            password: String,
+            user: String,
+        }
+
+        let options = ParseOptions::new().with_final_edit();
+        let mut session = Session::with_options(schema_for!(Login), options).unwrap();
+        let question = session.next_question().unwrap();
+        assert!(question.secret);
+        assert_eq!(question.answer_str(&json!("hunter2")), "********");
+        session.answer(json!("hunter2")).unwrap();
+        session.answer(json!("ada")).unwrap();
+
+        // The secret is hidden while editing the result, and kept unless
+        // it is replaced.
+        let question = session.next_question().unwrap();
+        assert_eq!(
+            question.default,
+            Some(json!(
+                "{\n  \"password\": \"********\",\n  \"user\": \"ada\"\n}"
+            ))
+        );
+        session
+            .answer(json!(r#"{"password": "********", "user": "bob"}"#))
+            .unwrap();
+        assert_eq!(
+            session.value(),
+            Some(&json!({ "password": "hunter2", "user": "bob" }))
+        );
+
+        let error = session.into_obj::<(String, String)>().unwrap_err();
+        assert!(!error.to_string().contains("hunter2"));
+        assert!(!format!("{error:?}").contains("hunter2"));
+    }
+
+    #[test]
+    fn test_secret_array() {
+        #[derive(JsonSchema, Deserialize)]
+        #[allow(dead_code)]
+        struct Tokens {
+            #[schemars(schema_with = "crate::secret::x_secret::<Vec<String>>")]
+            tokens: Vec<String>,
+        }
+
+        let options = ParseOptions::new().with_final_edit();
+        let mut session = Session::with_options(schema_for!(Tokens), options).unwrap();
+        session.answer(json!("Add element")).unwrap();
+        let question = session.next_question().unwrap();
+        assert_eq!(question.path, "/tokens/0");
+        assert!(question.secret);
+        session.answer(json!("t0ken")).unwrap();
+        session.answer(json!("Done")).unwrap();
+
+        // The masked array is put back before the edit is checked.
+        let question = session.next_question().unwrap();
+        let unchanged = question.default.clone().unwrap();
+        assert!(!unchanged.as_str().unwrap().contains("t0ken"));
+        assert_eq!(question.validate(&unchanged), Ok(()));
+        session.answer(unchanged).unwrap();
+        assert_eq!(session.value(), Some(&json!({ "tokens": ["t0ken"] })));
+        assert_eq!(
+            session.redact(&session.partial()),
+            json!({ "tokens": "********" })
+        );
+    }
+
+    #[test]
+    fn test_base64_string() {
+        fn base64(gen: &mut SchemaGenerator) -> Schema {
+            let mut schema = String::json_schema(gen).into_object();
+            schema
+                .extensions
+                .insert("contentEncoding".to_string(), json!("base64"));
+            schema.into()
+        }
+
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Blob {
+            #[schemars(schema_with = "base64")]
+            data: String,
+        }
+
+        let mut session = Session::for_type::<Blob>().unwrap();
+        assert_eq!(session.next_question().unwrap().choices.len(), 4);
+        session.answer(json!("Hex")).unwrap();
+        session.answer(json!("ff00")).unwrap();
+        assert_eq!(session.value(), Some(&json!({ "data": "/wA=" })));
+    }
+
+    #[test]
+    fn test_examples() {
+        fn region() -> &'static str {
+            "eu-west-1"
+        }
+
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Server {
+            #[schemars(example = "region")]
+            region: String,
+        }
+
+        let session = Session::for_type::<Server>().unwrap();
+        assert_eq!(
+            session.next_question().unwrap().examples,
+            vec![json!("eu-west-1")]
+        );
+    }
+
+    #[test]
+    fn test_choice_providers() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Branch(String);
+
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Checkout {
+            branch: Branch,
+            profiles: Vec<String>,
+        }
+
+        let options = ParseOptions::new()
+            .with_definition_choices("Branch", || vec!["main".to_string(), "dev".to_string()])
+            .with_path_choices("/profiles/*", || vec!["default".to_string()]);
+        let mut session = Session::with_options(schema_for!(Checkout), options).unwrap();
+        let question = session.next_question().unwrap();
+        assert_eq!(question.kind, QuestionKind::Select);
+        assert_eq!(question.choices, vec!["main", "dev"]);
+        assert!(session.answer(json!("feature")).is_err());
+        session.answer(json!("dev")).unwrap();
+
+        session.answer(json!("Add element")).unwrap();
+        assert_eq!(session.next_question().unwrap().choices, vec!["default"]);
+        session.answer(json!("default")).unwrap();
+        session.answer(json!("Done")).unwrap();
+        assert_eq!(
+            session.value(),
+            Some(&json!({ "branch": "dev", "profiles": ["default"] }))
+        );
+
+        // Choices can only be picked for strings.
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Retries(u8);
+
+        let options = ParseOptions::new().with_definition_choices("Retries", Vec::new);
+        assert!(matches!(
+            Session::with_options(schema_for!(Vec<Retries>), options),
+            Err(SchemaError::ChoicesNotString { name }) if name == "Retries"
+        ));
+    }
+
+    #[test]
+    fn test_changing_choices() {
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Branch(String);
+
+        #[derive(JsonSchema)]
+        #[allow(dead_code)]
+        struct Checkout {
+            branch: Branch,
+            profiles: Vec<String>,
+        }
+
+        // "dev" is deleted after the first two walks.
+        let calls = AtomicUsize::new(0);
+        let options = ParseOptions::new().with_definition_choices("Branch", move || {
+            match calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                true => vec!["main".to_string(), "dev".to_string()],
+                false => vec!["main".to_string()],
+            }
+        });
+        let mut session = Session::with_options(schema_for!(Checkout), options).unwrap();
+        session.answer(json!("dev")).unwrap();
+        assert_eq!(session.next_question().unwrap().path, "/profiles");
+
+        // The branch is asked for again, without the stale answers.
+        assert!(matches!(
+            session.answer(json!("Add element")),
+            Err(SchemaError::InvalidAnswer { path, .. }) if path == "/branch"
+        ));
+        assert_eq!(session.answers(), &[] as &[Value]);
+        assert_eq!(session.next_question().unwrap().choices, vec!["main"]);
+        session.answer(json!("main")).unwrap();
+        session.answer(json!("Done")).unwrap();
+        assert_eq!(
+            session.value(),
+            Some(&json!({ "branch": "main", "profiles": [] }))
+        );
+    }
+
+    #[test]
+    fn test_custom_prompt() {
+        #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+        struct Timeout {
+            secs: u64,
+        }
+
+        #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+        struct Job {
+            name: String,
+            timeout: Timeout,
+        }
+
+        struct TimeoutPrompt;
+
+        impl TimeoutPrompt {
+            fn secs(answer: &Value) -> Result<u64, String> {
+                let text = answer.as_str().unwrap_or_default();
+                let (amount, unit) = text.split_at(text.len().saturating_sub(1));
+                let amount = amount
+                    .parse::<u64>()
+                    .map_err(|_| "must be like 90s or 2m")?;
+                match unit {
+                    "s" => Ok(amount),
+                    "m" => Ok(amount * 60),
+                    _ => Err("must end in s or m".to_string()),
+                }
+            }
+        }
+
+        impl InteractivePrompt for TimeoutPrompt {
+            fn question(&self, name: &str, _schema: &SchemaObject) -> Question {
+                Question::new(QuestionKind::String, format!("{name}:"), "e.g. 90s or 2m")
+            }
+
+            fn validate(&self, answer: &Value) -> Result<(), String> {
+                Self::secs(answer).map(|_| ())
+            }
+
+            fn value(&self, answer: Value) -> Value {
+                json!({ "secs": Self::secs(&answer).unwrap() })
+            }
+        }
+
+        let options = ParseOptions::new().with_prompt_for::<Timeout>(TimeoutPrompt);
+        let mut session = Session::with_options(schema_for!(Job), options).unwrap();
+        session.answer(json!("backup")).unwrap();
+        let question = session.next_question().unwrap();
+        assert_eq!(question.kind, QuestionKind::String);
+        assert_eq!(question.message, "timeout:");
+        assert!(session.answer(json!("2h")).is_err());
+        session.answer(json!("2m")).unwrap();
+        assert_eq!(
+            session.into_obj::<Job>().unwrap(),
+            Job {
+                name: "backup".to_string(),
+                timeout: Timeout { secs: 120 },
+            }
+        );
+
+        // The root type is inlined, and is recognised by its title.
+        let options = ParseOptions::new().with_prompt_for::<Timeout>(TimeoutPrompt);
+        let mut session = Session::with_options(schema_for!(Timeout), options).unwrap();
+        session.answer(json!("90s")).unwrap();
+        assert_eq!(session.value(), Some(&json!({ "secs": 90 })));
+
+        let options = ParseOptions::new().with_prompt("Duration", TimeoutPrompt);
+        assert!(matches!(
+            Session::with_options(schema_for!(Job), options),
+            Err(SchemaError::UnusedPrompt { name }) if name == "Duration"
+        ));
+    }
+
+    #[test]
+    fn test_validator() {
+        #[derive(JsonSchema, Deserialize)]
+        #[allow(dead_code)]
+        struct Service {
+            name: String,
+            port: u16,
+        }
+
+        let options = ParseOptions::new().with_validator("/port", |port, service| {
+            match port == &json!(8080) {
+                true => Err(format!("8080 is already used by {}", service["name"])),
+                false => Ok(()),
+            }
+        });
+        let mut session = Session::with_options(schema_for!(Service), options.clone()).unwrap();
+        session.answer(json!("api")).unwrap();
+        let Err(SchemaError::InvalidAnswer { path, message }) = session.answer(json!(8080)) else {
+            panic!("expected the port to be rejected");
+        };
+        assert_eq!(path, "/port");
+        assert_eq!(message, "8080 is already used by \"api\"");
+        assert_eq!(session.next_question().unwrap().path, "/port");
+        session.answer(json!(8081)).unwrap();
+        assert_eq!(
+            session.value(),
+            Some(&json!({ "name": "api", "port": 8081 }))
+        );
+
+        // Fields entered as json are checked too.
+        let options = options.with_json_entry();
+        let mut session = Session::with_options(schema_for!(Service), options).unwrap();
+        assert!(matches!(
+            session.answer(json!(r#"{"name": "a", "port": 8080}"#)),
+            Err(SchemaError::InvalidAnswer { message, .. })
+                if message == "/port: 8080 is already used by \"a\""
+        ));
+        session
+            .answer(json!(r#"{"name": "a", "port": 8081}"#))
+            .unwrap();
+        assert_eq!(session.value(), Some(&json!({ "name": "a", "port": 8081 })));
+    }
+
+    #[test]
+    fn test_object_validator() {
+        #[derive(JsonSchema, Deserialize, Debug, PartialEq)]
+        struct Range {
+            max: u32,
+            min: u32,
+            step: u32,
+        }
+
+        fn in_order(range: &Value) -> Result<(), String> {
+            match range["min"].as_u64() <= range["max"].as_u64() {
+                true => Ok(()),
+                false => Err("min must not be more than max".to_string()),
+            }
+        }
+
+        let options = ParseOptions::new().with_object_validator("", ["min", "max"], in_order);
+        let mut session = Session::with_options(schema_for!(Range), options).unwrap();
+        session.answer(json!(5)).unwrap();
+        session.answer(json!(10)).unwrap();
+        session.answer(json!(1)).unwrap();
+
+        let question = session.next_question().unwrap();
+        assert_eq!(
+            question.message,
+            "min must not be more than max. Which field do you want to enter again?"
+        );
+        assert_eq!(question.choices, vec!["min", "max"]);
+        session.answer(json!("max")).unwrap();
+        assert_eq!(session.next_question().unwrap().path, "/max");
+        session.answer(json!(20)).unwrap();
+        assert_eq!(
+            session.into_obj::<Range>().unwrap(),
+            Range {
+                max: 20,
+                min: 10,
+                step: 1,
+            }
+        );
+        let options =
+            ParseOptions::new()
+                .with_json_entry()
+                .with_object_validator("", ["max"], in_order);
+        let mut session = Session::with_options(schema_for!(Range), options).unwrap();
+        assert!(matches!(
+            session.answer(json!(r#"{ "max": 1, "min": 5, "step": 1 }"#)),
+            Err(SchemaError::InvalidAnswer { message, .. })
+                if message == "min must not be more than max"
+        ));
+        session.answer(json!("")).unwrap();
+        session.answer(json!(5)).unwrap();
+        session.answer(json!(10)).unwrap();
+        session.answer(json!(1)).unwrap();
+        let question = session.next_question().unwrap();
+        assert_eq!(question.path, "/max");
+        assert!(question
+            .help
+            .starts_with("min must not be more than max, please enter max again"));
+    }
+}
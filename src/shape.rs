@@ -0,0 +1,185 @@
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use serde_json::Value;
+
+use crate::{
+    error::{SchemaError, SchemaResult},
+    get_schema_object_ref,
+    prompt::QuestionKind,
+};
+
+/// The shape of a value described by a schema, for frontends that show a
+/// whole value at once rather than asking one question at a time, and for
+/// looking ahead at what a value contains.
+#[cfg_attr(not(any(feature = "form", feature = "web")), allow(dead_code))]
+pub(crate) enum Shape {
+    Leaf(QuestionKind),
+    Object(Vec<(String, SchemaObject)>),
+    /// The schema of the value when it is present.
+    Optional(SchemaObject),
+    /// Each variant's name, and what it holds.
+    Enum(Vec<(String, Variant)>),
+    Array {
+        item: SchemaObject,
+        min_items: u32,
+        max_items: Option<u32>,
+    },
+    Tuple(Vec<SchemaObject>),
+}
+
+/// What a variant of an enum holds.
+#[cfg_attr(not(any(feature = "form", feature = "web")), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub(crate) enum Variant {
+    /// The json value of a unit variant, which is its name unless the enum
+    /// lists other values, such as numbers.
+    Unit(Value),
+    /// The schema of the contents of a variant with data.
+    Contents(SchemaObject),
+}
+
+/// Resolves `schema` and works out the shape of the value it describes.
+pub(crate) fn shape(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+) -> SchemaResult<(SchemaObject, Shape)> {
+    let schema = resolve(definitions, schema)?;
+
+    // Enums of only unit variants.
+    if let Some(enum_values) = &schema.enum_values {
+        let variants = enum_values
+            .iter()
+            .map(|value| match value {
+                Value::String(name) => (name.clone(), Variant::Unit(value.clone())),
+                value => (value.to_string(), Variant::Unit(value.clone())),
+            })
+            .collect();
+        return Ok((schema, Shape::Enum(variants)));
+    }
+
+    let instance_type = match &schema.instance_type {
+        Some(SingleOrVec::Single(instance_type)) => Some(**instance_type),
+        Some(SingleOrVec::Vec(vec)) => {
+            // An optional regular type.
+            let mut inner = schema.clone();
+            inner.instance_type = vec
+                .iter()
+                .find(|x| x != &&InstanceType::Null)
+                .map(|x| SingleOrVec::Single(Box::new(*x)));
+            return Ok((schema, Shape::Optional(inner)));
+        }
+        None => None,
+    };
+
+    let shape = match instance_type {
+        Some(InstanceType::String) => Shape::Leaf(QuestionKind::String),
+        Some(InstanceType::Integer) => Shape::Leaf(QuestionKind::Integer),
+        Some(InstanceType::Number) => Shape::Leaf(QuestionKind::Number),
+        Some(InstanceType::Boolean) => Shape::Leaf(QuestionKind::Boolean),
+        Some(InstanceType::Object) => {
+            let mut fields = Vec::new();
+            if let Some(object_info) = &schema.object {
+                for (name, property) in &object_info.properties {
+                    fields.push((name.clone(), get_schema_object_ref(property)?.clone()));
+                }
+            }
+            Shape::Object(fields)
+        }
+        Some(InstanceType::Array) => {
+            let array_info = schema.array.clone().unwrap_or_default();
+            match array_info.items {
+                Some(SingleOrVec::Single(item)) => Shape::Array {
+                    item: get_schema_object_ref(&item)?.clone(),
+                    min_items: array_info.min_items.unwrap_or_default(),
+                    max_items: array_info.max_items,
+                },
+                Some(SingleOrVec::Vec(items)) => Shape::Tuple(
+                    items
+                        .iter()
+                        .map(|item| get_schema_object_ref(item).cloned())
+                        .collect::<SchemaResult<_>>()?,
+                ),
+                None => return Err(SchemaError::Unimplemented),
+            }
+        }
+        Some(InstanceType::Null) | None => {
+            let Some(subschemas) = &schema.subschemas else {
+                return Err(SchemaError::Unimplemented);
+            };
+            if let Some(one_of) = &subschemas.one_of {
+                let mut variants = Vec::new();
+                for variant in one_of {
+                    let variant = get_schema_object_ref(variant)?;
+                    if let Some(object_info) = &variant.object {
+                        let (name, contents) = object_info
+                            .properties
+                            .iter()
+                            .next()
+                            .ok_or(SchemaError::Unimplemented)?;
+                        variants.push((
+                            name.clone(),
+                            Variant::Contents(get_schema_object_ref(contents)?.clone()),
+                        ));
+                    } else if let Some(Value::String(name)) =
+                        variant.enum_values.as_ref().and_then(|x| x.first())
+                    {
+                        variants.push((name.clone(), Variant::Unit(Value::String(name.clone()))));
+                    } else {
+                        return Err(SchemaError::Unimplemented);
+                    }
+                }
+                Shape::Enum(variants)
+            } else if let Some(any_of) = &subschemas.any_of {
+                let non_null = any_of
+                    .iter()
+                    .map(get_schema_object_ref)
+                    .collect::<SchemaResult<Vec<_>>>()?
+                    .into_iter()
+                    .find(|x| {
+                        x.instance_type != Some(SingleOrVec::Single(Box::new(InstanceType::Null)))
+                    })
+                    .ok_or(SchemaError::Unimplemented)?;
+                Shape::Optional(non_null.clone())
+            } else {
+                return Err(SchemaError::Unimplemented);
+            }
+        }
+    };
+    Ok((schema, shape))
+}
+
+/// Follows `$ref`s and single element `allOf`s to the schema that describes
+/// the value.
+fn resolve(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+) -> SchemaResult<SchemaObject> {
+    if let Some(reference) = &schema.reference {
+        let reference = reference
+            .strip_prefix("#/definitions/")
+            .unwrap_or(reference);
+        return match definitions.get(reference) {
+            Some(Schema::Object(schema)) => resolve(definitions, schema),
+            Some(Schema::Bool(_)) => Err(SchemaError::SchemaIsBool),
+            None => Err(SchemaError::Unimplemented),
+        };
+    }
+    if let Some(all_of) = schema.subschemas.as_ref().and_then(|x| x.all_of.as_ref()) {
+        if let [Schema::Object(inner)] = all_of.as_slice() {
+            let mut inner = resolve(definitions, inner)?;
+            if inner.metadata.is_none() {
+                inner.metadata = schema.metadata.clone();
+            }
+            return Ok(inner);
+        }
+    }
+    Ok(schema.clone())
+}
+
+/// The doc comment on `schema`, if any.
+#[cfg(any(feature = "form", feature = "web"))]
+pub(crate) fn description(schema: &SchemaObject) -> Option<String> {
+    schema
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.description.clone())
+}
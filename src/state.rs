@@ -0,0 +1,313 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+};
+
+use inquire::InquireError;
+use log::debug;
+use schemars::schema::{RootSchema, Schema, SchemaObject};
+use serde_json::{Map, Value};
+
+use crate::{
+    error::{SchemaError, SchemaResult},
+    options::ParseOptions,
+    prompt::{prompt_terminal, Question},
+    resume::SessionFile,
+    secret,
+    undo::Undo,
+};
+
+/// State shared by every step of a single parse.
+pub(crate) struct ParseState<'a> {
+    pub(crate) definitions: &'a schemars::Map<String, Schema>,
+    /// The schema of the whole value, used to hide its secrets.
+    root: &'a SchemaObject,
+    pub(crate) options: &'a ParseOptions,
+    pub(crate) current_depth: Cell<u16>,
+    /// Every answer given so far, indexed by the depth it was given at, as
+    /// saved to the session file. Secrets are saved as `null`.
+    answers: RefCell<Vec<Value>>,
+    /// Answers from a previous session that are used instead of prompting.
+    replay: RefCell<VecDeque<Value>>,
+    /// How many of the answers in `replay` have been used. Replay stops at
+    /// the first answer that doesn't fit its question.
+    replayed: Cell<usize>,
+    session_file: Option<SessionFile>,
+    /// The json pointer segments of the value currently being parsed.
+    path: RefCell<Vec<String>>,
+    /// Every value completed so far, along with the depth it was completed
+    /// at and its json pointer. Used to rebuild the partial value when the
+    /// user exits early.
+    completed: RefCell<Vec<(u16, String, Value)>>,
+    /// Whether the terminal is off limits, in which case the first question
+    /// without a replayed answer is stored in `pending` instead of prompted.
+    detached: bool,
+    pending: RefCell<Option<Question>>,
+    /// A message for the user, shown in the help of the next question.
+    notice: RefCell<Option<String>>,
+    /// Whether the value being parsed is part of a secret, which makes every
+    /// question about it a secret too.
+    secret: Cell<bool>,
+}
+
+impl<'a> ParseState<'a> {
+    pub(crate) fn new(
+        root_schema: &'a RootSchema,
+        options: &'a ParseOptions,
+        session_file: Option<SessionFile>,
+        replay: Vec<Value>,
+    ) -> Self {
+        Self {
+            definitions: &root_schema.definitions,
+            root: &root_schema.schema,
+            options,
+            current_depth: Cell::new(0),
+            answers: RefCell::new(Vec::new()),
+            replay: RefCell::new(replay.into()),
+            replayed: Cell::new(0),
+            session_file,
+            path: RefCell::new(Vec::new()),
+            completed: RefCell::new(Vec::new()),
+            detached: false,
+            pending: RefCell::new(None),
+            notice: RefCell::new(None),
+            secret: Cell::new(false),
+        }
+    }
+
+    /// A state that never touches the terminal. Questions past the end of
+    /// `answers` stop the parse with [`SchemaError::Unanswered`].
+    pub(crate) fn detached(
+        root_schema: &'a RootSchema,
+        options: &'a ParseOptions,
+        answers: Vec<Value>,
+    ) -> Self {
+        Self {
+            detached: true,
+            ..Self::new(root_schema, options, None, answers)
+        }
+    }
+
+    /// How many replayed answers the parse has used so far.
+    pub(crate) fn replayed(&self) -> usize {
+        self.replayed.get()
+    }
+
+    /// The question that stopped a detached parse.
+    pub(crate) fn take_pending(&self) -> Option<Question> {
+        self.pending.borrow_mut().take()
+    }
+
+    /// The json pointer of the value currently being parsed.
+    pub(crate) fn pointer(&self) -> String {
+        self.path
+            .borrow()
+            .iter()
+            .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+            .collect()
+    }
+
+    /// Runs `f` with `segment` appended to the current path.
+    pub(crate) fn with_segment<T>(
+        &self,
+        segment: impl ToString,
+        f: impl FnOnce() -> SchemaResult<T>,
+    ) -> SchemaResult<T> {
+        self.path.borrow_mut().push(segment.to_string());
+        let result = f();
+        self.path.borrow_mut().pop();
+        result
+    }
+
+    /// Runs `f`, marking every question it asks as a secret if `secret`,
+    /// such as the elements of a secret array.
+    pub(crate) fn with_secret<T>(
+        &self,
+        secret: bool,
+        f: impl FnOnce() -> SchemaResult<T>,
+    ) -> SchemaResult<T> {
+        let outer = self.secret.replace(self.secret.get() || secret);
+        let result = f();
+        self.secret.set(outer);
+        result
+    }
+
+    /// Records that the value at the current path is complete.
+    pub(crate) fn complete(&self, value: &Value) {
+        self.completed
+            .borrow_mut()
+            .push((self.current_depth.get(), self.pointer(), value.clone()));
+    }
+
+    /// Everything the user has entered so far, assembled into a single value.
+    pub(crate) fn partial(&self) -> Value {
+        let mut partial = Value::Null;
+        for (_, pointer, value) in self.completed.borrow().iter() {
+            insert_at(&mut partial, pointer, value.clone());
+        }
+        partial
+    }
+
+    /// Runs `parse` until the value it returns passes `check`. On the
+    /// terminal a rejected value is explained and parsed again from scratch,
+    /// while a detached parse stops with [`SchemaError::InvalidAnswer`] so
+    /// that the last answer can be taken back.
+    pub(crate) fn check(
+        &self,
+        parse: impl Fn() -> SchemaResult<Value>,
+        check: impl Fn(&Value) -> Result<(), String>,
+    ) -> SchemaResult<Value> {
+        let depth_checkpoint = self.current_depth.get();
+        loop {
+            let value = parse()?;
+            let Err(message) = check(&value) else {
+                return Ok(value);
+            };
+            if self.detached {
+                return Err(SchemaError::InvalidAnswer {
+                    path: self.pointer(),
+                    message,
+                });
+            }
+            self.replay.borrow_mut().clear();
+            self.current_depth.set(depth_checkpoint);
+            self.notify(format!("{message}, please try again"));
+        }
+    }
+
+    /// Shows `message` in the help of the next question, rather than
+    /// printing it between the prompts.
+    pub(crate) fn notify(&self, message: String) {
+        *self.notice.borrow_mut() = Some(message);
+    }
+
+    /// Asks a question, either by replaying a saved answer or by prompting.
+    pub(crate) fn ask(&self, mut question: Question) -> SchemaResult<Value> {
+        question.path = self.pointer();
+        question.secret |= self.secret.get();
+        if let Some(notice) = self.notice.borrow_mut().take() {
+            question = question.with_notice(notice);
+        }
+        // Anything past the current depth has been undone.
+        let depth = self.current_depth.get();
+        self.answers.borrow_mut().truncate(depth as usize);
+        self.completed
+            .borrow_mut()
+            .retain(|(completed_depth, _, _)| *completed_depth <= depth);
+        if let Some(session_file) = &self.session_file {
+            session_file.save(&self.answers.borrow())?;
+        }
+
+        let replayed = self.replay.borrow_mut().pop_front();
+        let answer = match replayed {
+            // Secrets are not saved, so they are asked for again.
+            Some(Value::Null) if question.secret => self.prompt(&question)?,
+            Some(answer) if question.accepts(&answer) => {
+                self.replayed.set(self.replayed.get() + 1);
+                if !self.detached {
+                    // Written where inquire writes its prompts, so that it
+                    // is cleared along with them.
+                    eprintln!("> {} {}", question.message, question.answer_str(&answer));
+                }
+                Some(answer)
+            }
+            Some(answer) => {
+                debug!(
+                    "replayed answer {} does not fit, prompting instead",
+                    question.answer_str(&answer)
+                );
+                self.replay.borrow_mut().clear();
+                self.prompt(&question)?
+            }
+            None => self.prompt(&question)?,
+        }
+        .undo(&self.current_depth)?;
+        let answer = question
+            .normalize(answer)
+            .map_err(|message| SchemaError::InvalidAnswer {
+                path: question.path.clone(),
+                message,
+            })?;
+
+        self.answers.borrow_mut().push(match question.secret {
+            true => Value::Null,
+            false => answer.clone(),
+        });
+        if let Some(session_file) = &self.session_file {
+            session_file.save(&self.answers.borrow())?;
+        }
+        Ok(answer)
+    }
+
+    fn prompt(&self, question: &Question) -> SchemaResult<Option<Value>> {
+        if self.detached {
+            *self.pending.borrow_mut() = Some(question.clone());
+            return Err(SchemaError::Unanswered);
+        }
+        match prompt_terminal(question) {
+            Err(SchemaError::Inquire(
+                InquireError::OperationInterrupted | InquireError::OperationCanceled,
+            )) => Err(SchemaError::Exit {
+                partial: secret::redact(self.definitions, self.root, &self.partial()),
+                path: question.path.clone(),
+            }),
+            other => other,
+        }
+    }
+}
+
+/// Sets the value at `pointer`, creating any missing parents along the way.
+fn insert_at(target: &mut Value, pointer: &str, value: Value) {
+    let Some(rest) = pointer.strip_prefix('/') else {
+        *target = value;
+        return;
+    };
+    let (segment, rest) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, ""),
+    };
+    let segment = segment.replace("~1", "/").replace("~0", "~");
+    if target.is_null() {
+        *target = match segment.parse::<usize>() {
+            Ok(_) => Value::Array(Vec::new()),
+            Err(_) => Value::Object(Map::new()),
+        };
+    }
+    let child = match target {
+        Value::Array(array) => match segment.parse::<usize>() {
+            Ok(index) => {
+                if array.len() <= index {
+                    array.resize(index + 1, Value::Null);
+                }
+                &mut array[index]
+            }
+            Err(_) => return,
+        },
+        Value::Object(map) => map.entry(segment).or_insert(Value::Null),
+        _ => return,
+    };
+    insert_at(child, rest, value);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::insert_at;
+
+    #[test]
+    fn test_insert_at() {
+        let mut partial = Value::Null;
+        insert_at(&mut partial, "/my_vec/1", json!(2));
+        insert_at(&mut partial, "/my_enum/StructVariant/floats", json!([1.0]));
+        insert_at(&mut partial, "/a~1b", json!(true));
+        assert_eq!(
+            partial,
+            json!({
+                "my_vec": [null, 2],
+                "my_enum": { "StructVariant": { "floats": [1.0] } },
+                "a/b": true,
+            })
+        );
+    }
+}
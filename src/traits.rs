@@ -1,41 +1,75 @@
-use std::cell::Cell;
+#[cfg(feature = "async")]
+use std::future::Future;
 
-use schemars::{schema_for, JsonSchema};
+use schemars::{schema::SchemaObject, schema_for, JsonSchema};
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 
-use crate::{error::SchemaResult, parse_schema};
+#[cfg(feature = "async")]
+use crate::async_prompt::{parse_async, AsyncPrompter};
+#[cfg(feature = "validator")]
+use crate::revalidate::parse_validated;
+use crate::{
+    error::{from_value, SchemaResult},
+    options::ParseOptions,
+    parse_root,
+    prompt::Question,
+};
 
 pub trait InteractiveParseVal
 where
     Self: Sized,
 {
-    fn parse_to_val() -> SchemaResult<Value>;
+    fn parse_to_val() -> SchemaResult<Value> {
+        Self::parse_to_val_with(&ParseOptions::default())
+    }
+
+    fn parse_to_val_with(options: &ParseOptions) -> SchemaResult<Value>;
+
+    /// Parses without blocking, asking every question through `prompter`.
+    #[cfg(feature = "async")]
+    fn parse_to_val_async<P>(prompter: &mut P) -> impl Future<Output = SchemaResult<Value>> + Send
+    where
+        P: AsyncPrompter + Send;
+
+    /// Like `parse_to_val_async`, asking the questions the way `options`
+    /// asks for. Options that pick a different frontend are ignored.
+    /// Validators and choice providers are called from blocking tasks, so
+    /// they may block.
+    #[cfg(feature = "async")]
+    fn parse_to_val_async_with<P>(
+        options: &ParseOptions,
+        prompter: &mut P,
+    ) -> impl Future<Output = SchemaResult<Value>> + Send
+    where
+        P: AsyncPrompter + Send;
 }
 
 impl<T> InteractiveParseVal for T
 where
     T: JsonSchema,
 {
-    fn parse_to_val() -> SchemaResult<Value> {
-        let root_schema = schema_for!(T);
-        let name = String::default();
-        let mut title = None;
-        if let Some(metadata) = &root_schema.schema.metadata {
-            if let Some(title_ref) = &metadata.title {
-                title = Some(title_ref.clone());
-            }
-        }
-
-        let value = parse_schema(
-            &root_schema.definitions,
-            title,
-            name,
-            root_schema.schema,
-            &Cell::new(0),
-        )?;
-
-        Ok(value)
+    fn parse_to_val_with(options: &ParseOptions) -> SchemaResult<Value> {
+        parse_root(schema_for!(T), options)
+    }
+
+    #[cfg(feature = "async")]
+    fn parse_to_val_async<P>(prompter: &mut P) -> impl Future<Output = SchemaResult<Value>> + Send
+    where
+        P: AsyncPrompter + Send,
+    {
+        parse_async(schema_for!(T), ParseOptions::default(), prompter)
+    }
+
+    #[cfg(feature = "async")]
+    fn parse_to_val_async_with<P>(
+        options: &ParseOptions,
+        prompter: &mut P,
+    ) -> impl Future<Output = SchemaResult<Value>> + Send
+    where
+        P: AsyncPrompter + Send,
+    {
+        parse_async(schema_for!(T), options.clone(), prompter)
     }
 }
 
@@ -43,21 +77,107 @@ pub trait InteractiveParseObj
 where
     Self: Sized,
 {
-    fn parse_to_obj() -> SchemaResult<Self>;
+    fn parse_to_obj() -> SchemaResult<Self> {
+        Self::parse_to_obj_with(&ParseOptions::default())
+    }
+
+    fn parse_to_obj_with(options: &ParseOptions) -> SchemaResult<Self>;
+
+    /// Parses without blocking, asking every question through `prompter`.
+    #[cfg(feature = "async")]
+    fn parse_to_obj_async<P>(prompter: &mut P) -> impl Future<Output = SchemaResult<Self>> + Send
+    where
+        P: AsyncPrompter + Send;
+
+    /// Like `parse_to_obj_async`, asking the questions the way `options`
+    /// asks for. Options that pick a different frontend are ignored.
+    /// Validators and choice providers are called from blocking tasks, so
+    /// they may block.
+    #[cfg(feature = "async")]
+    fn parse_to_obj_async_with<P>(
+        options: &ParseOptions,
+        prompter: &mut P,
+    ) -> impl Future<Output = SchemaResult<Self>> + Send
+    where
+        P: AsyncPrompter + Send;
 }
 
 impl<T> InteractiveParseObj for T
 where
     T: JsonSchema + DeserializeOwned,
 {
-    fn parse_to_obj() -> SchemaResult<Self> {
-        let value = Self::parse_to_val()?;
-        let my_struct = serde_json::from_value::<T>(value.clone()).map_err(|e| {
-            crate::error::SchemaError::Serde {
-                value,
-                serde_error: e,
-            }
-        })?;
-        Ok(my_struct)
+    fn parse_to_obj_with(options: &ParseOptions) -> SchemaResult<Self> {
+        let value = Self::parse_to_val_with(options)?;
+        from_value(&schema_for!(T), value)
+    }
+
+    #[cfg(feature = "async")]
+    async fn parse_to_obj_async<P>(prompter: &mut P) -> SchemaResult<Self>
+    where
+        P: AsyncPrompter + Send,
+    {
+        let value = Self::parse_to_val_async(prompter).await?;
+        from_value(&schema_for!(T), value)
+    }
+
+    #[cfg(feature = "async")]
+    async fn parse_to_obj_async_with<P>(
+        options: &ParseOptions,
+        prompter: &mut P,
+    ) -> SchemaResult<Self>
+    where
+        P: AsyncPrompter + Send,
+    {
+        let value = Self::parse_to_val_async_with(options, prompter).await?;
+        from_value(&schema_for!(T), value)
+    }
+}
+
+/// Parses types that carry `validator` rules, asking again for just the
+/// fields that break them until the value passes `Validate::validate`. The
+/// fields are asked for on the terminal, so options that pick another
+/// frontend fail with [`SchemaError::TerminalOnly`](crate::error::SchemaError::TerminalOnly).
+///
+/// Only the `validator` crate's rules are checked. `garde` rules are not.
+#[cfg(feature = "validator")]
+pub trait InteractiveParseValidated
+where
+    Self: Sized,
+{
+    fn parse_to_obj_validated() -> SchemaResult<Self> {
+        Self::parse_to_obj_validated_with(&ParseOptions::default())
+    }
+
+    fn parse_to_obj_validated_with(options: &ParseOptions) -> SchemaResult<Self>;
+}
+
+#[cfg(feature = "validator")]
+impl<T> InteractiveParseValidated for T
+where
+    T: JsonSchema + DeserializeOwned + validator::Validate,
+{
+    fn parse_to_obj_validated_with(options: &ParseOptions) -> SchemaResult<Self> {
+        parse_validated(options)
+    }
+}
+
+/// A bespoke prompt for values of one type, such as a url, a duration or a
+/// domain specific newtype, which is asked instead of the questions the
+/// type's schema would lead to. Register it with
+/// [`ParseOptions::with_prompt`] or [`ParseOptions::with_prompt_for`].
+pub trait InteractivePrompt: Send + Sync {
+    /// The question to ask for the value called `name`, whose schema is
+    /// `schema`.
+    fn question(&self, name: &str, schema: &SchemaObject) -> Question;
+
+    /// Checks an answer before it is accepted, explaining what is wrong with
+    /// it if anything.
+    fn validate(&self, _answer: &Value) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// The json value of the type for an accepted answer.
+    fn value(&self, answer: Value) -> Value {
+        answer
     }
 }
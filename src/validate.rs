@@ -0,0 +1,383 @@
+use std::collections::BTreeMap;
+
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use serde_json::Value;
+
+use crate::{
+    options::{self, Callback, ObjectValidator, Validator as PathValidator},
+    prompt::Constraints,
+    secret,
+};
+
+/// Checks `value` against `schema`, following `$ref`s into `definitions`.
+/// Errors start with the json pointer of the offending value.
+pub(crate) fn validate_value(
+    definitions: &schemars::Map<String, Schema>,
+    schema: &SchemaObject,
+    value: &Value,
+) -> Result<(), String> {
+    Validator {
+        definitions,
+        secrets: secret::secret_pointers(definitions, schema, value),
+    }
+    .object(schema, value, "")
+}
+
+/// Runs the object validators registered for `value`, which is the value at
+/// the json pointer `pointer`, and for every object inside it. Errors start
+/// with the json pointer of the rejected object.
+pub(crate) fn check_objects(
+    validators: &BTreeMap<String, Callback<ObjectValidator>>,
+    pointer: &str,
+    value: &Value,
+) -> Result<(), String> {
+    for (child_pointer, child) in children(pointer, value) {
+        check_objects(validators, &child_pointer, child)?;
+    }
+    match (value, options::at_path(validators, pointer)) {
+        (Value::Object(_), Some(validator)) => {
+            (validator.check)(value).map_err(|message| error(pointer, &message))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runs the path validators registered for `value`, which is the value at
+/// the json pointer `pointer` within `parent`, and for every value inside
+/// it. Errors start with the json pointer of the rejected value.
+pub(crate) fn check_paths(
+    validators: &BTreeMap<String, Callback<PathValidator>>,
+    pointer: &str,
+    value: &Value,
+    parent: &Value,
+) -> Result<(), String> {
+    for (child_pointer, child) in children(pointer, value) {
+        check_paths(validators, &child_pointer, child, value)?;
+    }
+    match options::at_path(validators, pointer) {
+        Some(validator) => validator(value, parent).map_err(|message| error(pointer, &message)),
+        None => Ok(()),
+    }
+}
+
+/// The values directly inside `value`, which is the value at `pointer`,
+/// along with their json pointers.
+fn children<'a>(pointer: &str, value: &'a Value) -> Vec<(String, &'a Value)> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, child)| {
+                let segment = key.replace('~', "~0").replace('/', "~1");
+                (format!("{pointer}/{segment}"), child)
+            })
+            .collect(),
+        Value::Array(elements) => elements
+            .iter()
+            .enumerate()
+            .map(|(i, child)| (format!("{pointer}/{i}"), child))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+struct Validator<'a> {
+    definitions: &'a schemars::Map<String, Schema>,
+    /// The json pointers of the secrets in the value being checked.
+    secrets: Vec<String>,
+}
+
+impl Validator<'_> {
+    fn schema(&self, schema: &Schema, value: &Value, path: &str) -> Result<(), String> {
+        match schema {
+            Schema::Bool(true) => Ok(()),
+            Schema::Bool(false) => Err(error(path, "is not allowed")),
+            Schema::Object(schema) => self.object(schema, value, path),
+        }
+    }
+
+    fn object(&self, schema: &SchemaObject, value: &Value, path: &str) -> Result<(), String> {
+        let result = self.checks(schema, value, path);
+        match self.is_secret(path) {
+            true => result.map_err(|message| mask(&message, path, value)),
+            false => result,
+        }
+    }
+
+    /// Whether the value at `path` is a secret or inside one.
+    fn is_secret(&self, path: &str) -> bool {
+        self.secrets.iter().any(|secret| {
+            path.strip_prefix(secret.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+
+    fn checks(&self, schema: &SchemaObject, value: &Value, path: &str) -> Result<(), String> {
+        if let Some(reference) = &schema.reference {
+            let name = reference
+                .strip_prefix("#/definitions/")
+                .unwrap_or(reference);
+            let definition = self
+                .definitions
+                .get(name)
+                .ok_or_else(|| error(path, &format!("unknown definition {name}")))?;
+            self.schema(definition, value, path)?;
+        }
+
+        if let Some(enum_values) = &schema.enum_values {
+            if !enum_values.contains(value) {
+                let expected = enum_values
+                    .iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(error(path, &format!("must be one of {expected}")));
+            }
+        }
+        if let Some(const_value) = &schema.const_value {
+            if const_value != value {
+                return Err(error(path, &format!("must be {const_value}")));
+            }
+        }
+
+        if let Some(instance_type) = &schema.instance_type {
+            let fits = match instance_type {
+                SingleOrVec::Single(instance_type) => is_instance(instance_type, value),
+                SingleOrVec::Vec(instance_types) => instance_types
+                    .iter()
+                    .any(|instance_type| is_instance(instance_type, value)),
+            };
+            if !fits {
+                return Err(error(path, &format!("{value} has the wrong type")));
+            }
+        }
+        Constraints::from_schema(schema)
+            .check(value)
+            .map_err(|message| error(path, &message))?;
+
+        if let (Some(array_info), Value::Array(elements)) = (&schema.array, value) {
+            match &array_info.items {
+                Some(SingleOrVec::Single(item)) => {
+                    for (i, element) in elements.iter().enumerate() {
+                        self.schema(item, element, &format!("{path}/{i}"))?;
+                    }
+                }
+                Some(SingleOrVec::Vec(items)) => {
+                    if elements.len() != items.len() {
+                        return Err(error(
+                            path,
+                            &format!("must have exactly {} elements", items.len()),
+                        ));
+                    }
+                    for (i, (item, element)) in items.iter().zip(elements).enumerate() {
+                        self.schema(item, element, &format!("{path}/{i}"))?;
+                    }
+                }
+                None => {}
+            }
+        }
+
+        if let (Some(object_info), Value::Object(map)) = (&schema.object, value) {
+            for required in &object_info.required {
+                if !map.contains_key(required) {
+                    return Err(error(path, &format!("is missing {required}")));
+                }
+            }
+            for (key, property) in map {
+                let property_path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+                match (
+                    object_info.properties.get(key),
+                    &object_info.additional_properties,
+                ) {
+                    (Some(schema), _) => self.schema(schema, property, &property_path)?,
+                    (None, Some(schema)) => self.schema(schema, property, &property_path)?,
+                    (None, None) => {}
+                }
+            }
+        }
+
+        if let Some(subschemas) = &schema.subschemas {
+            if let Some(all_of) = &subschemas.all_of {
+                for schema in all_of {
+                    self.schema(schema, value, path)?;
+                }
+            }
+            if let Some(any_of) = &subschemas.any_of {
+                let results = any_of
+                    .iter()
+                    .map(|schema| self.schema(schema, value, path))
+                    .collect::<Vec<_>>();
+                if !results.iter().any(Result::is_ok) {
+                    return Err(closest_error(results.into_iter()));
+                }
+            }
+            if let Some(one_of) = &subschemas.one_of {
+                let results = one_of
+                    .iter()
+                    .map(|schema| self.schema(schema, value, path))
+                    .collect::<Vec<_>>();
+                match results.iter().filter(|result| result.is_ok()).count() {
+                    1 => {}
+                    0 => return Err(closest_error(results.into_iter())),
+                    _ => return Err(error(path, "matches more than one variant")),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_instance(instance_type: &InstanceType, value: &Value) -> bool {
+    match instance_type {
+        InstanceType::Null => value.is_null(),
+        InstanceType::Boolean => value.is_boolean(),
+        InstanceType::Object => value.is_object(),
+        InstanceType::Array => value.is_array(),
+        InstanceType::Number => value.is_number(),
+        InstanceType::String => value.is_string(),
+        InstanceType::Integer => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|x| x.fract() == 0.0)
+        }
+    }
+}
+
+/// The error from the alternative that got the furthest into the value,
+/// which is usually the one the user meant.
+fn closest_error(results: impl Iterator<Item = Result<(), String>>) -> String {
+    results
+        .filter_map(Result::err)
+        .max_by_key(|error| {
+            error
+                .split_once(": ")
+                .filter(|(path, _)| path.starts_with('/'))
+                .map_or(0, |(path, _)| path.matches('/').count())
+        })
+        .unwrap_or_else(|| "does not match any alternative".to_string())
+}
+
+/// `message`, an error about the secret `value` at `path`, with `value`
+/// masked. Errors about values inside it have already masked them, and only
+/// the text after the path is masked so that the path stays readable.
+fn mask(message: &str, path: &str, value: &Value) -> String {
+    let prefix = match path {
+        "" => "",
+        path => message
+            .strip_prefix(path)
+            .filter(|rest| rest.starts_with(": "))
+            .map_or("", |_| path),
+    };
+    let (prefix, rest) = message.split_at(prefix.len());
+    format!("{prefix}{}", rest.replace(&value.to_string(), secret::MASK))
+}
+
+fn error(path: &str, message: &str) -> String {
+    match path {
+        "" => message.to_string(),
+        path => format!("{path}: {message}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{schema_for, JsonSchema};
+    use serde_json::json;
+
+    use super::{check_objects, check_paths, validate_value};
+    use crate::options::ParseOptions;
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    enum Mode {
+        Fast,
+        Slow { delay: u8 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Job {
+        name: String,
+        mode: Mode,
+        retries: Option<u32>,
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_validate_value() {
+        let root = schema_for!(Job);
+        let validate = |value| validate_value(&root.definitions, &root.schema, &value);
+        assert_eq!(
+            validate(json!({ "name": "a", "mode": "Fast", "tags": [] })),
+            Ok(())
+        );
+        assert_eq!(
+            validate(json!({ "name": "a", "mode": { "Slow": { "delay": 300 } }, "tags": [] })),
+            Err("/mode/Slow/delay: must be at most 255".to_string())
+        );
+        assert_eq!(
+            validate(json!({ "name": "a", "mode": "Fast", "tags": [1] })),
+            Err("/tags/0: 1 has the wrong type".to_string())
+        );
+        assert_eq!(
+            validate(json!({ "mode": "Fast", "tags": [] })),
+            Err("is missing name".to_string())
+        );
+    }
+
+    #[test]
+    fn test_secrets() {
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        struct Login {
+            #[schemars(schema_with = "crate::secret::x_secret::<Vec<u8>>")]
+            pins: Vec<u8>,
+        }
+
+        let root = schema_for!(Login);
+        let validate = |value| validate_value(&root.definitions, &root.schema, &value);
+        assert_eq!(validate(json!({ "pins": [1, 2] })), Ok(()));
+        assert_eq!(
+            validate(json!({ "pins": [1, 300] })),
+            Err("/pins/1: must be at most 255".to_string())
+        );
+        assert_eq!(
+            validate(json!({ "pins": [1, "20"] })),
+            Err("/pins/1: ******** has the wrong type".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_objects() {
+        let options = ParseOptions::new().with_object_validator("/ranges/*", ["min"], |range| {
+            match range["min"].as_u64() <= range["max"].as_u64() {
+                true => Ok(()),
+                false => Err("min must not be more than max".to_string()),
+            }
+        });
+        let check = |value| check_objects(&options.object_validators, "", &value);
+        assert_eq!(check(json!({ "ranges": [{ "min": 1, "max": 2 }] })), Ok(()));
+        assert_eq!(
+            check(json!({ "ranges": [{ "min": 1, "max": 2 }, { "min": 3, "max": 2 }] })),
+            Err("/ranges/1: min must not be more than max".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_paths() {
+        let options = ParseOptions::new().with_validator("/servers/*/port", |port, server| {
+            match port == &json!(8080) {
+                true => Err(format!("8080 is already used by {}", server["name"])),
+                false => Ok(()),
+            }
+        });
+        let check = |value| check_paths(&options.validators, "", &value, &json!(null));
+        assert_eq!(
+            check(json!({ "servers": [{ "name": "a", "port": 80 }] })),
+            Ok(())
+        );
+        assert_eq!(
+            check(
+                json!({ "servers": [{ "name": "a", "port": 80 }, { "name": "b", "port": 8080 }] })
+            ),
+            Err("/servers/1/port: 8080 is already used by \"b\"".to_string())
+        );
+    }
+}
@@ -0,0 +1,689 @@
+use std::{
+    collections::BTreeMap,
+    hash::{BuildHasher, RandomState},
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use log::debug;
+use schemars::{
+    schema::{RootSchema, Schema, SchemaObject},
+    schema_for, JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::{SchemaError, SchemaResult},
+    get_root_title,
+    prompt::{Question, QuestionKind},
+    shape::{description, shape, Shape, Variant},
+    validate::validate_value,
+};
+
+/// How long a client may take to send its request. Requests are answered
+/// one at a time, so a stalled client holds up everyone else until then.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The largest request body accepted, which is far more than any form needs.
+const MAX_BODY_LEN: usize = 1 << 20;
+
+/// The most elements an array without `maxItems` can have, so that a
+/// request can't make the server render an endless form.
+const MAX_ELEMENTS: usize = 1000;
+
+/// Serves an html form generated from a schema on a local address, and
+/// returns the value once a valid form is submitted.
+///
+/// The form is rendered on the server, so it works without javascript:
+/// adding array elements, switching enum variants and toggling optionals
+/// re-render the form with everything entered so far.
+///
+/// Only loopback addresses can be bound, since anyone who can reach the form
+/// can fill it in. Every form carries a random token that submissions have
+/// to send back, so other sites open in the browser can't post to it.
+pub struct WebForm {
+    listener: TcpListener,
+    root_schema: RootSchema,
+    token: String,
+}
+
+impl WebForm {
+    /// Binds to `addr`, e.g. `"127.0.0.1:0"` to pick any free port. Fails
+    /// with [`SchemaError::NotLoopback`] for any other kind of address.
+    pub fn bind(root_schema: RootSchema, addr: impl ToSocketAddrs) -> SchemaResult<Self> {
+        let addrs = addr.to_socket_addrs()?.collect::<Vec<_>>();
+        if let Some(addr) = addrs.iter().find(|addr| !addr.ip().is_loopback()) {
+            return Err(SchemaError::NotLoopback { addr: *addr });
+        }
+        Ok(Self {
+            listener: TcpListener::bind(&addrs[..])?,
+            root_schema,
+            token: random_token(),
+        })
+    }
+
+    pub fn bind_type<T: JsonSchema>(addr: impl ToSocketAddrs) -> SchemaResult<Self> {
+        Self::bind(schema_for!(T), addr)
+    }
+
+    pub fn local_addr(&self) -> SchemaResult<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Answers requests until a valid form is submitted.
+    pub fn serve(self) -> SchemaResult<Value> {
+        for stream in self.listener.incoming() {
+            let mut stream = stream?;
+            let (method, path, body) = match read_request(&mut stream) {
+                Ok(request) => request,
+                Err(e) => {
+                    debug!("ignoring bad request: {e}");
+                    continue;
+                }
+            };
+            if path != "/" {
+                respond(&mut stream, "404 Not Found", "")?;
+                continue;
+            }
+
+            let first = method != "POST";
+            let mut values = parse_urlencoded(&body);
+            if !first && values.remove("token").as_ref() != Some(&self.token) {
+                respond(&mut stream, "403 Forbidden", "")?;
+                continue;
+            }
+            let action = values.remove("action").unwrap_or_default();
+            if let Some(pointer) = action.strip_prefix("add:") {
+                let len = array_len(&values, pointer);
+                values.insert(format!("{pointer}#len"), (len + 1).to_string());
+            } else if let Some(element) = action.strip_prefix("remove:") {
+                remove_element(&mut values, element);
+            }
+
+            let mut render = Render {
+                definitions: &self.root_schema.definitions,
+                values,
+                first,
+                show_errors: action == "submit",
+                html: String::new(),
+                errors: 0,
+            };
+            let title = get_root_title(&self.root_schema).unwrap_or_default();
+            let value = render.node(&self.root_schema.schema, "$", &title)?;
+            // Every field is valid on its own, but the value as a whole can
+            // still have too many elements or repeated ones in a set.
+            let error = match action == "submit" && render.errors == 0 {
+                true => match validate_value(
+                    &self.root_schema.definitions,
+                    &self.root_schema.schema,
+                    &value,
+                ) {
+                    Ok(()) => {
+                        respond(
+                            &mut stream,
+                            "200 OK",
+                            &page(&title, "<p>Submitted, you can close this page.</p>"),
+                        )?;
+                        return Ok(value);
+                    }
+                    Err(message) => format!(
+                        "<p><strong class=\"error\">{}</strong></p>",
+                        escape(&message)
+                    ),
+                },
+                false => String::new(),
+            };
+            let form = format!(
+                "{error}<form method=\"post\">\
+                 <input type=\"hidden\" name=\"token\" value=\"{}\">{}\
+                 <p><button name=\"action\" value=\"refresh\">Update</button> \
+                 <button name=\"action\" value=\"submit\">Submit</button></p></form>",
+                self.token, render.html
+            );
+            respond(&mut stream, "200 OK", &page(&title, &form))?;
+        }
+        unreachable!("TcpListener::incoming never ends")
+    }
+}
+
+/// Renders the form and builds the value from the submitted fields in a
+/// single pass over the schema. Fields are named by `$` followed by their
+/// json pointer.
+struct Render<'a> {
+    definitions: &'a schemars::Map<String, Schema>,
+    values: BTreeMap<String, String>,
+    /// Whether this is the first time the form is shown, in which case
+    /// defaults are filled in.
+    first: bool,
+    show_errors: bool,
+    html: String,
+    errors: usize,
+}
+
+impl Render<'_> {
+    fn node(&mut self, schema: &SchemaObject, name: &str, label: &str) -> SchemaResult<Value> {
+        let (schema, shape) = shape(self.definitions, schema)?;
+        let help = description(&schema)
+            .map(|description| format!(" <small>{}</small>", escape(&description)))
+            .unwrap_or_default();
+        let value = match shape {
+            Shape::Leaf(kind) => {
+                let question = Question::new(kind, label, "").with_schema(&schema);
+                let input = match self.values.get(name) {
+                    Some(input) => input.clone(),
+                    None => match &question.default {
+                        Some(Value::String(default)) if self.first => default.clone(),
+                        Some(default) if self.first => default.to_string(),
+                        _ if kind == QuestionKind::Boolean => "false".to_string(),
+                        _ => String::new(),
+                    },
+                };
+                let input_html = match kind {
+                    QuestionKind::String if question.widget.as_deref() == Some("textarea") => {
+                        format!(
+                            "<textarea name=\"{}\" rows=\"6\" cols=\"60\">{}</textarea>",
+                            escape(name),
+                            escape(&input)
+                        )
+                    }
+                    QuestionKind::Boolean => format!(
+                        "<input type=\"checkbox\" name=\"{}\" value=\"true\"{}>",
+                        escape(name),
+                        if input == "true" { " checked" } else { "" }
+                    ),
+                    kind => {
+                        let examples = question
+                            .examples
+                            .iter()
+                            .map(|example| match example {
+                                Value::String(example) => example.clone(),
+                                example => example.to_string(),
+                            })
+                            .map(|example| format!("<option value=\"{}\">", escape(&example)))
+                            .collect::<String>();
+                        format!(
+                            "<input type=\"{}\" name=\"{}\" value=\"{}\"{}",
+                            match kind {
+                                QuestionKind::Integer | QuestionKind::Number => {
+                                    "number\" step=\"any"
+                                }
+                                _ if question.secret => "password",
+                                _ if question.widget.as_deref() == Some("date") => "date",
+                                _ => "text",
+                            },
+                            escape(name),
+                            // Secrets are never sent back to the browser.
+                            match question.secret {
+                                true => String::new(),
+                                false => escape(&input),
+                            },
+                            match examples.is_empty() {
+                                true => ">".to_string(),
+                                false => format!(
+                                    " list=\"{0}-examples\"><datalist id=\"{0}-examples\">{examples}</datalist>",
+                                    escape(name)
+                                ),
+                            }
+                        )
+                    }
+                };
+                let result = question.parse(&input);
+                let error = match &result {
+                    Err(error) if self.show_errors => {
+                        format!(" <strong class=\"error\">{}</strong>", escape(error))
+                    }
+                    _ => String::new(),
+                };
+                self.errors += result.is_err() as usize;
+                self.html.push_str(&format!(
+                    "<div><label>{} {input_html}</label>{error}{help}</div>",
+                    escape(label)
+                ));
+                result.unwrap_or_default()
+            }
+            Shape::Object(properties) => {
+                self.html.push_str(&format!(
+                    "<fieldset><legend>{}</legend>{help}",
+                    escape(label)
+                ));
+                let mut map = Map::new();
+                for (property, schema) in properties {
+                    let value = self.node(
+                        &schema,
+                        &format!("{name}/{}", pointer_segment(&property)),
+                        &property,
+                    )?;
+                    map.insert(property, value);
+                }
+                self.html.push_str("</fieldset>");
+                Value::Object(map)
+            }
+            Shape::Optional(schema) => {
+                let some_name = format!("{name}#some");
+                let some = self.values.get(&some_name).is_some_and(|x| x == "true");
+                self.html.push_str(&format!(
+                    "<div><label><input type=\"checkbox\" name=\"{}\" value=\"true\"{} \
+                     onchange=\"this.form.requestSubmit()\"> {}</label>{help}</div>",
+                    escape(&some_name),
+                    if some { " checked" } else { "" },
+                    escape(label)
+                ));
+                if some {
+                    self.node(&schema, name, label)?
+                } else {
+                    Value::Null
+                }
+            }
+            Shape::Enum(variants) => {
+                let variant_name = format!("{name}#variant");
+                let selected = self
+                    .values
+                    .get(&variant_name)
+                    .and_then(|x| variants.iter().position(|(name, _)| name == x))
+                    .unwrap_or_default();
+                let options = variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (variant, _))| {
+                        format!(
+                            "<option{}>{}</option>",
+                            if i == selected { " selected" } else { "" },
+                            escape(variant)
+                        )
+                    })
+                    .collect::<String>();
+                self.html.push_str(&format!(
+                    "<div><label>{} <select name=\"{}\" onchange=\"this.form.requestSubmit()\">\
+                     {options}</select></label>{help}</div>",
+                    escape(label),
+                    escape(&variant_name)
+                ));
+                match &variants[selected] {
+                    (variant, Variant::Contents(schema)) => {
+                        let inner_name = format!("{name}/{}", pointer_segment(variant));
+                        json!({ variant.clone(): self.node(schema, &inner_name, variant)? })
+                    }
+                    (_, Variant::Unit(value)) => value.clone(),
+                }
+            }
+            Shape::Array {
+                item,
+                min_items,
+                max_items,
+            } => {
+                let requested = array_len(&self.values, name).max(min_items as usize);
+                let max = max_items.map_or(MAX_ELEMENTS, |max| (max as usize).min(MAX_ELEMENTS));
+                let len = requested.min(max);
+                self.html.push_str(&format!(
+                    "<fieldset><legend>{}</legend>{help}\
+                     <input type=\"hidden\" name=\"{}#len\" value=\"{len}\">",
+                    escape(label),
+                    escape(name)
+                ));
+                if requested > max {
+                    self.errors += 1;
+                    self.html.push_str(&format!(
+                        "<strong class=\"error\">only {max} elements are allowed</strong>"
+                    ));
+                }
+                let mut elements = Vec::new();
+                for i in 0..len {
+                    let element_name = format!("{name}/{i}");
+                    elements.push(self.node(&item, &element_name, &format!("[{i}]"))?);
+                    if len > min_items as usize {
+                        self.html.push_str(&format!(
+                            "<button name=\"action\" value=\"remove:{}\">Remove [{i}]</button>",
+                            escape(&element_name)
+                        ));
+                    }
+                }
+                if max_items.is_none_or(|max| len < max as usize) {
+                    self.html.push_str(&format!(
+                        "<button name=\"action\" value=\"add:{}\">Add element</button>",
+                        escape(name)
+                    ));
+                }
+                self.html.push_str("</fieldset>");
+                Value::Array(elements)
+            }
+            Shape::Tuple(items) => {
+                self.html.push_str(&format!(
+                    "<fieldset><legend>{}</legend>{help}",
+                    escape(label)
+                ));
+                let mut elements = Vec::new();
+                for (i, item) in items.iter().enumerate() {
+                    elements.push(self.node(item, &format!("{name}/{i}"), &format!("[{i}]"))?);
+                }
+                self.html.push_str("</fieldset>");
+                Value::Array(elements)
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// A token that can't be guessed from outside the process, from the random
+/// keys std seeds its hash maps with.
+fn random_token() -> String {
+    let state = RandomState::new();
+    format!(
+        "{:016x}{:016x}",
+        state.hash_one(std::process::id()),
+        RandomState::new().hash_one(std::time::SystemTime::now())
+    )
+}
+
+fn array_len(values: &BTreeMap<String, String>, name: &str) -> usize {
+    values
+        .get(&format!("{name}#len"))
+        .and_then(|len| len.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Removes the array element named `element`, shifting the fields of the
+/// elements after it down by one.
+fn remove_element(values: &mut BTreeMap<String, String>, element: &str) {
+    let Some((array, index)) = element.rsplit_once('/') else {
+        return;
+    };
+    let Ok(index) = index.parse::<usize>() else {
+        return;
+    };
+    let len = array_len(values, array);
+    let prefix = format!("{array}/");
+    let mut shifted = BTreeMap::new();
+    for (name, value) in std::mem::take(values) {
+        let Some(rest) = name.strip_prefix(&prefix) else {
+            shifted.insert(name, value);
+            continue;
+        };
+        let (i, tail) = rest.split_at(rest.find(['/', '#']).unwrap_or(rest.len()));
+        match i.parse::<usize>() {
+            Ok(i) if i == index => {}
+            Ok(i) if i > index => {
+                shifted.insert(format!("{prefix}{}{tail}", i - 1), value);
+            }
+            _ => {
+                shifted.insert(name, value);
+            }
+        }
+    }
+    shifted.insert(format!("{array}#len"), len.saturating_sub(1).to_string());
+    *values = shifted;
+}
+
+fn pointer_segment(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{0}</title>\
+         <style>fieldset {{ margin: 0.5em 0; }} .error {{ color: red; }}</style></head>\
+         <body><h1>{0}</h1>{body}</body></html>",
+        escape(title)
+    )
+}
+
+fn read_request(stream: &mut TcpStream) -> std::io::Result<(String, String, String)> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    // Also bounds the request line and headers, which are read up to the
+    // next line break.
+    let mut reader = BufReader::new(Read::take(stream, (MAX_BODY_LEN * 2) as u64));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or_default();
+            }
+        }
+    }
+    if content_length > MAX_BODY_LEN {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("the body of {content_length} bytes is too large"),
+        ));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok((method, path, String::from_utf8_lossy(&body).into_owned()))
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> SchemaResult<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/html; charset=utf-8\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+fn parse_urlencoded(body: &str) -> BTreeMap<String, String> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(name), percent_decode(value))
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = [iter.next().unwrap_or(b'0'), iter.next().unwrap_or(b'0')];
+                let hex = std::str::from_utf8(&hex).unwrap_or("00");
+                bytes.push(u8::from_str_radix(hex, 16).unwrap_or(b'?'));
+            }
+            byte => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{SocketAddr, TcpStream},
+        thread,
+    };
+
+    use schemars::JsonSchema;
+    use serde_json::json;
+
+    use super::{WebForm, MAX_BODY_LEN};
+    use crate::{error::SchemaError, ParseOptions};
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    enum Mode {
+        Fast,
+        Slow { delay: u8 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    struct Job {
+        name: String,
+        mode: Mode,
+        retries: Option<u32>,
+        tags: Vec<String>,
+    }
+
+    /// Sends `body` along with `token`, or gets the form if there is none.
+    fn request(addr: SocketAddr, token: &str, body: Option<&str>) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        match body.map(|body| format!("{body}&token={token}")) {
+            Some(body) => write!(
+                stream,
+                "POST / HTTP/1.1\r\nHost: localhost\r\n\
+                 Content-Type: application/x-www-form-urlencoded\r\n\
+                 Content-Length: {}\r\n\r\n{body}",
+                body.len()
+            ),
+            None => write!(stream, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"),
+        }
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn token(page: &str) -> String {
+        let field = "name=\"token\" value=\"";
+        let start = page.find(field).unwrap() + field.len();
+        page[start..start + 32].to_string()
+    }
+
+    #[test]
+    fn test_web_form() {
+        let form = WebForm::bind_type::<Job>("127.0.0.1:0").unwrap();
+        let addr = form.local_addr().unwrap();
+        let server = thread::spawn(move || form.serve());
+
+        let page = request(addr, "", None);
+        let token = token(&page);
+        assert!(page.contains("name=\"$/name\""));
+        assert!(page.contains("<option selected>Fast</option><option>Slow</option>"));
+
+        // Switching the variant and adding an element re-render the form.
+        let page = request(
+            addr,
+            &token,
+            Some("%24%2Fmode%23variant=Slow&%24%2Fname=build&action=add%3A%24%2Ftags"),
+        );
+        assert!(page.contains("name=\"$/mode/Slow/delay\""));
+        assert!(page.contains("name=\"$/tags/0\""));
+        assert!(page.contains("value=\"build\""));
+
+        // The delay does not fit in a u8.
+        let fields = "%24%2Fmode%23variant=Slow&%24%2Fmode%2FSlow%2Fdelay=300\
+                      &%24%2Fname=build&%24%2Ftags%23len=1&%24%2Ftags%2F0=nightly+build";
+        let page = request(addr, &token, Some(&format!("{fields}&action=submit")));
+        assert!(page.contains("must be at most 255"));
+
+        // Posts that don't carry the form's token are turned away.
+        let page = request(addr, "forged", Some(&format!("{fields}&action=submit")));
+        assert!(page.starts_with("HTTP/1.1 403"));
+        // So are arrays longer than they can be.
+        let page = request(
+            addr,
+            &token,
+            Some("%24%2Ftags%23len=999999999&action=refresh"),
+        );
+        assert!(page.contains("only 1000 elements are allowed"));
+
+        let fields = fields.replace("300", "30");
+        let page = request(addr, &token, Some(&format!("{fields}&action=submit")));
+        assert!(page.contains("Submitted"));
+        assert_eq!(
+            server.join().unwrap().unwrap(),
+            json!({
+                "mode": { "Slow": { "delay": 30 } },
+                "name": "build",
+                "retries": null,
+                "tags": ["nightly build"],
+            })
+        );
+    }
+
+    #[test]
+    fn test_whole_value() {
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        struct Tags {
+            #[schemars(skip_serializing)]
+            password: String,
+            #[schemars(length(max = 2))]
+            tags: std::collections::BTreeSet<String>,
+        }
+
+        assert!(matches!(
+            WebForm::bind_type::<Tags>("0.0.0.0:0"),
+            Err(SchemaError::NotLoopback { .. })
+        ));
+        let form = WebForm::bind_type::<Tags>("127.0.0.1:0").unwrap();
+        let addr = form.local_addr().unwrap();
+        let server = thread::spawn(move || form.serve());
+
+        // A body that is too large is dropped without an answer.
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_LEN + 1
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert_eq!(response, "");
+
+        let token = token(&request(addr, "", None));
+        let page = request(
+            addr,
+            &token,
+            Some("%24%2Fpassword=hunter2&%24%2Ftags%23len=2&%24%2Ftags%2F0=a&%24%2Ftags%2F1=a&action=submit"),
+        );
+        assert!(page.contains("/tags: "));
+        // The password has to be typed in again.
+        assert!(page.contains("name=\"$/password\" value=\"\""));
+        assert!(!page.contains("hunter2"));
+        let page = request(
+            addr,
+            &token,
+            Some("%24%2Fpassword=hunter2&%24%2Ftags%23len=3&%24%2Ftags%2F0=a&%24%2Ftags%2F1=b&%24%2Ftags%2F2=c&action=submit"),
+        );
+        assert!(page.contains("only 2 elements are allowed"));
+
+        let page = request(
+            addr,
+            &token,
+            Some("%24%2Fpassword=hunter2&%24%2Ftags%23len=2&%24%2Ftags%2F0=a&%24%2Ftags%2F1=b&action=submit"),
+        );
+        assert!(page.contains("Submitted"));
+        assert_eq!(
+            server.join().unwrap().unwrap(),
+            json!({ "password": "hunter2", "tags": ["a", "b"] })
+        );
+    }
+
+    #[test]
+    fn test_unsupported_options() {
+        let options = ParseOptions::new()
+            .with_web_form("127.0.0.1:0".parse().unwrap())
+            .with_definition_choices("Mode", Vec::new);
+        assert!(matches!(
+            crate::parse_root(schemars::schema_for!(Job), &options),
+            Err(SchemaError::UnsupportedOption {
+                frontend: "web form",
+                option: "choice providers"
+            })
+        ));
+    }
+}